@@ -0,0 +1,53 @@
+//! Decoding cost per sample
+//!
+//! Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use manchester_code::{
+    ActivityLevel, BitOrder, Datagram, DatagramBigEndianIterator, DecoderBuilder, Encoder,
+    SyncOnTurningEdge,
+};
+
+/// Samples of an RC5 like 14 bit datagram on an active low receiver
+fn frame_samples(samples_per_half_bit: usize) -> Vec<bool> {
+    let datagram = Datagram::new("11_0_10100_011111");
+    let idle = vec![true; 12 * samples_per_half_bit];
+    let mut samples = idle.clone();
+    for half_bit in Encoder::<DatagramBigEndianIterator>::new(datagram) {
+        samples.extend(std::iter::repeat(!half_bit).take(samples_per_half_bit));
+    }
+    samples.extend(idle);
+    samples
+}
+
+fn decode_frame(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_frame");
+    for samples_per_half_bit in [3_u8, 9, 27] {
+        let samples = frame_samples(samples_per_half_bit as usize);
+        let mut decoder = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_samples_per_half_bit(samples_per_half_bit)
+        .build()
+        .unwrap();
+        assert_eq!(1, decoder.bench_feed(&samples));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(samples_per_half_bit),
+            &samples,
+            |b, samples| {
+                b.iter(|| {
+                    let count = decoder.bench_feed(black_box(samples));
+                    // keep the double buffer free
+                    while decoder.take_completed().is_some() {}
+                    count
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode_frame);
+criterion_main!(benches);
@@ -12,6 +12,10 @@
 //! * Sampling needs to be 3 times the length of half a bit. (i.e. only a
 //!   single periodic timer is needed), for a infrared receiver
 //!   889 µs halfbit period => the periodic timer should run all 297 µs.
+//! * `defmt::Format` for datagrams with the default `defmt` cargo feature,
+//!   opt out via `default-features = false`
+//! * `serde::Serialize` and `serde::Deserialize` for datagrams with the
+//!   `serde` cargo feature
 //!
 //! # Manchester Modulation
 //!
@@ -91,13 +95,24 @@
 // #![deny(warnings)]
 #![deny(unsafe_code)]
 
+#[cfg(any(test, feature = "sim"))]
+extern crate alloc;
+
+#[cfg(feature = "defmt")]
 use defmt::Format;
 
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
 use core::iter::Iterator;
 use core::ops::Index;
 
+use embedded_hal::digital::v2::InputPin;
 use embedded_hal::Pwm;
 
+use heapless::spsc::Producer;
+use heapless::Deque;
+use heapless::String;
+
 /// BitOrder or endian describes the ordering of bits during transmission
 ///
 /// Big endian:  MSB is transmitted first; LSB is transmitted last
@@ -108,21 +123,89 @@ pub enum BitOrder {
     LittleEndian,
 }
 
+/// Parity of a group of bits including its parity bit
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Parity {
+    /// The number of set bits is even
+    Even,
+    /// The number of set bits is odd
+    Odd,
+}
+
 /// Representation of a datagram
 ///
-/// The total length is limited to 128 bits
+/// The total length is limited to 128 bits ([`Datagram::MAX_BITS`])
 /// The bits of a telegram are internally enumerated from 0 to 127.
 /// A default datagram is expected to be empty (i.e. containing zero bits)
 #[derive(Default, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "SerializedDatagram", into = "SerializedDatagram")
+)]
 pub struct Datagram {
     length_in_bit: u8,
     buffer: u128,
 }
 
-#[derive(Debug)]
-struct Error;
+/// Serialized form of a datagram, e.g. `{"len":4,"buffer":11}` in JSON
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedDatagram {
+    len: u8,
+    buffer: u128,
+}
+
+#[cfg(feature = "serde")]
+impl From<Datagram> for SerializedDatagram {
+    fn from(datagram: Datagram) -> Self {
+        SerializedDatagram {
+            len: datagram.length_in_bit,
+            buffer: datagram.buffer,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl core::convert::TryFrom<SerializedDatagram> for Datagram {
+    type Error = &'static str;
+
+    fn try_from(serialized: SerializedDatagram) -> Result<Self, Self::Error> {
+        if serialized.len > Datagram::MAX_BITS {
+            Err("datagram longer than 128 bits")
+        } else if serialized
+            .buffer
+            .checked_shr(serialized.len as u32)
+            .unwrap_or(0)
+            != 0
+        {
+            Err("datagram buffer has bits beyond its length")
+        } else {
+            Ok(Datagram {
+                length_in_bit: serialized.len,
+                buffer: serialized.buffer,
+            })
+        }
+    }
+}
+
+/// The capacity of a datagram (128 bits) is exceeded
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Error;
+
+/// Reasons why a string is not a valid datagram representation
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// A character that is neither a bit nor a delimiter, with its byte offset
+    IllegalCharacter { position: usize, character: char },
+    /// More than 128 bit characters are present
+    TooManyBits,
+}
 
 impl Datagram {
+    /// The maximum number of bits of a datagram
+    pub const MAX_BITS: u8 = u128::BITS as u8;
+
     /// Add a bit to a datagram
     ///
     /// The new bit is placed at index zero.
@@ -132,14 +215,14 @@ impl Datagram {
     ///
     /// * `bit` - The bit value to record at index 0
     /// * `bit_order`- The bit order either BigEndian or LittleEndian determines
-    ///                if the bit is added at the LSB or MSB position
+    ///   if the bit is added at the LSB or MSB position
     ///
     /// # Returns
     ///
     /// * Error - if the datagram is already filled up to its capacity.
     /// * () - if the bit was successfully added
     fn add_bit(&mut self, bit: bool, order: BitOrder) -> Result<(), Error> {
-        if self.length_in_bit == 127 {
+        if self.length_in_bit >= Datagram::MAX_BITS {
             Err(Error)
         } else {
             match order {
@@ -168,8 +251,84 @@ impl Datagram {
         0 == self.length_in_bit
     }
 
+    /// Compare the bits of two datagrams regardless of their length
+    ///
+    /// Unlike `==`, which also compares the lengths, leading zeros (in big
+    /// endian order) do not matter, e.g. `"01"` matches `"0001"`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// assert!(Datagram::new("01").matches(&Datagram::new("0001")));
+    /// assert_ne!(Datagram::new("01"), Datagram::new("0001"));
+    /// ```
+    pub fn matches(&self, other: &Datagram) -> bool {
+        self.buffer == other.buffer
+    }
+
+    /// Compare selected bits of two datagrams regardless of their length
+    ///
+    /// Like [`Datagram::matches`] but only the bits set in `mask` are
+    /// compared, the index of a bit in the mask is its index in the datagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The datagram to compare with
+    /// * `mask` - The bits to compare
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// // ignore the toggle bit of RC5
+    /// let first = Datagram::new("11_0_00000_001100");
+    /// let second = Datagram::new("11_1_00000_001100");
+    /// assert!(first.matches_mask(&second, !(1 << 11)));
+    /// ```
+    pub fn matches_mask(&self, other: &Datagram, mask: u128) -> bool {
+        (self.buffer ^ other.buffer) & mask == 0
+    }
+
+    /// Number of zeros transmitted first in big endian order
+    ///
+    /// Only the bits within the length of the datagram are counted, i.e.
+    /// an all-zero datagram has as many leading zeros as bits.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// assert_eq!(3, Datagram::new("0001").leading_zeros());
+    /// assert_eq!(4, Datagram::new("0000").leading_zeros());
+    /// ```
+    pub fn leading_zeros(&self) -> u8 {
+        let unused_bits = Datagram::MAX_BITS as u32 - self.length_in_bit as u32;
+        (self.buffer.leading_zeros() - unused_bits) as u8
+    }
+
+    /// Number of bits without the leading zeros
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// assert_eq!(1, Datagram::new("0001").significant_bits());
+    /// assert_eq!(4, Datagram::new("1000").significant_bits());
+    /// ```
+    pub fn significant_bits(&self) -> u8 {
+        self.length_in_bit - self.leading_zeros()
+    }
+
     /// Extract a data slice from the datagram
     ///
+    /// The bits with the indices `min` to `max - 1` are extracted, bit
+    /// `max - 1` is the MSB and bit `min` the LSB of the result.
+    ///
     /// # Args
     ///
     /// * `min` - start index (included)
@@ -193,21 +352,244 @@ impl Datagram {
     /// assert_eq!(0b1111, datagram.extract_data(datagram.len()-5, datagram.len()));
     /// ```
     pub fn extract_data(&self, min: u8, max: u8) -> u128 {
-        if max > self.length_in_bit {
-            panic!("Max index to big");
+        match self.try_extract_data(min, max) {
+            Ok(value) => value,
+            Err(_) => panic!("Index range out of bounds"),
+        }
+    }
+
+    /// Extract a data slice from the datagram without panicking
+    ///
+    /// Like [`Datagram::extract_data`], but an invalid range is reported.
+    ///
+    /// # Returns
+    ///
+    /// * Error - if 0 <= min < max <= len() is violated
+    /// * the extracted value otherwise
+    ///
+    /// # Example
+    /// ```rust
+    ///
+    /// use manchester_code::Datagram;
+    ///
+    /// let datagram = Datagram::new("1011");
+    /// assert_eq!(Ok(0b11), datagram.try_extract_data(0, 2));
+    /// assert!(datagram.try_extract_data(0, 5).is_err());
+    /// ```
+    pub fn try_extract_data(&self, min: u8, max: u8) -> Result<u128, Error> {
+        if max > self.length_in_bit || min >= max {
+            return Err(Error);
+        }
+        let width = (max - min) as u32;
+        Ok((self.buffer >> min) & (u128::MAX >> (128 - width)))
+    }
+
+    /// Access the n-th bit without panicking
+    ///
+    /// # Returns
+    ///
+    /// * None - if the index is out of range
+    /// * Some(bit) - the bit as 0 or 1 like for [`Index`]
+    ///
+    /// # Example
+    /// ```rust
+    ///
+    /// use manchester_code::Datagram;
+    ///
+    /// let datagram = Datagram::new("10");
+    /// assert_eq!(Some(0), datagram.get(0));
+    /// assert_eq!(Some(1), datagram.get(1));
+    /// assert_eq!(None, datagram.get(2));
+    /// ```
+    pub fn get(&self, index: u8) -> Option<u128> {
+        if index >= self.length_in_bit {
+            None
+        } else {
+            Some((self.buffer >> index) & 1)
+        }
+    }
+
+    /// Check if a small field is in a set of allowed values
+    ///
+    /// The field is extracted like by [`Datagram::extract_data`] with
+    /// `min = offset` and `max = offset + width`. The value is allowed if
+    /// the bit with the index of the value is set in `allowed_mask`, i.e.
+    /// fields of up to 6 bits are supported. Larger values are never allowed.
+    ///
+    /// # Panics
+    ///  if 0 <= offset < offset + width <= len() is violated
+    ///
+    /// # Example
+    /// ```rust
+    ///
+    /// use manchester_code::Datagram;
+    ///
+    /// let allowed = 1 << 12 | 1 << 16;
+    /// assert!(Datagram::new("11_0_00000_001100").value_in_mask(0, 6, allowed));
+    /// assert!(!Datagram::new("11_0_00000_001101").value_in_mask(0, 6, allowed));
+    /// ```
+    pub fn value_in_mask(&self, offset: u8, width: u8, allowed_mask: u64) -> bool {
+        let value = self.extract_data(offset, offset + width);
+        value < u64::BITS as u128 && (allowed_mask >> value) & 1 == 1
+    }
+
+    /// Split the datagram into a header count and fixed-size records
+    ///
+    /// The leading `header_bits` (i.e. the ones with the highest indices,
+    /// transmitted first in big endian order) hold the number of records.
+    /// The records follow the header, each one `record_bits` wide,
+    /// and are yielded in order of transmission (big endian).
+    ///
+    /// * If the datagram is too short for the header or `record_bits` is
+    ///   zero, no record is yielded.
+    /// * If the datagram is too short for `count` records, only the
+    ///   completely available records are yielded.
+    /// * Bits following the last counted record are ignored.
+    ///
+    /// # Example
+    /// ```rust
+    ///
+    /// use manchester_code::Datagram;
+    ///
+    /// let datagram = Datagram::new("0010_0101_1100");
+    /// let mut records = datagram.records(4, 4);
+    /// assert_eq!(Some(0b0101), records.next());
+    /// assert_eq!(Some(0b1100), records.next());
+    /// assert_eq!(None, records.next());
+    /// ```
+    pub fn records(&self, header_bits: u8, record_bits: u8) -> impl Iterator<Item = u128> + '_ {
+        let (count, available) = if header_bits == 0 || header_bits > self.length_in_bit {
+            (0, 0)
+        } else {
+            let body_len = self.length_in_bit - header_bits;
+            let available = body_len.checked_div(record_bits).unwrap_or(0);
+            (self.extract_data(body_len, self.length_in_bit), available)
+        };
+        let count = count.min(available as u128) as u8;
+        let body_end = self.length_in_bit.saturating_sub(header_bits);
+        (0..count).map(move |index| {
+            let max = body_end - index * record_bits;
+            self.extract_data(max - record_bits, max)
+        })
+    }
+
+    /// Extract a two's complement signed data slice from the datagram
+    ///
+    /// Works like [`Datagram::extract_data`] but the bit at `max - 1` is
+    /// taken as sign bit of the `max - min` wide field, i.e. the value is
+    /// sign extended.
+    ///
+    /// # Panics
+    ///  if 0 <= min < max <= len() is violated
+    ///
+    /// # Example
+    /// ```rust
+    ///
+    /// use manchester_code::Datagram;
+    ///
+    /// let datagram = Datagram::new("1110_0110");
+    /// assert_eq!(-2, datagram.extract_signed(4, 8));
+    /// assert_eq!(6, datagram.extract_signed(0, 4));
+    /// ```
+    pub fn extract_signed(&self, min: u8, max: u8) -> i128 {
+        let value = self.extract_data(min, max);
+        let width = max - min;
+        if width < Datagram::MAX_BITS && (value >> (width - 1)) & 1 == 1 {
+            value as i128 - (1_i128 << width)
+        } else {
+            value as i128
+        }
+    }
+
+    /// Append the bits of another datagram
+    ///
+    /// The end of a datagram is the bit transmitted last (in big endian
+    /// order), i.e. index 0. The bits of `other` take the indices 0 to
+    /// `other.len() - 1` and the bits of `self` are moved up by `other.len()`.
+    ///
+    /// # Returns
+    ///
+    /// * Error - if the combined length exceeds 128 bits, `self` is unchanged
+    /// * () - if the bits were appended
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// let mut datagram = Datagram::new("110");
+    /// datagram.append(&Datagram::new("01")).unwrap();
+    /// assert_eq!(Datagram::new("110_01"), datagram);
+    /// ```
+    pub fn append(&mut self, other: &Datagram) -> Result<(), Error> {
+        let length_in_bit = self.length_in_bit as u16 + other.length_in_bit as u16;
+        if length_in_bit > Datagram::MAX_BITS as u16 {
+            return Err(Error);
         }
-        if min >= max {
-            panic!("Min index to greater than max index");
+        self.buffer = self
+            .buffer
+            .checked_shl(other.length_in_bit as u32)
+            .unwrap_or(0)
+            | other.buffer;
+        self.length_in_bit = length_in_bit as u8;
+        Ok(())
+    }
+
+    /// Append the lowest `count` bits of a value
+    ///
+    /// The bits are appended like by [`Datagram::append`], the most
+    /// significant of them is transmitted first (in big endian order).
+    /// Higher bits of `value` are ignored.
+    ///
+    /// # Returns
+    ///
+    /// * Error - if the combined length exceeds 128 bits, `self` is unchanged
+    /// * () - if the bits were appended
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// let mut datagram = Datagram::new("11");
+    /// datagram.push_bits(0b1111_0010, 5).unwrap();
+    /// assert_eq!(Datagram::new("11_10010"), datagram);
+    /// ```
+    pub fn push_bits(&mut self, value: u128, count: u8) -> Result<(), Error> {
+        if count > Datagram::MAX_BITS {
+            return Err(Error);
         }
+        let mask = u128::MAX.checked_shr(128 - count as u32).unwrap_or(0);
+        self.append(&Datagram {
+            length_in_bit: count,
+            buffer: value & mask,
+        })
+    }
 
-        let mut value = 0_u128;
-        for index in min..max {
-            let mask: u128 = 1 << (max + min - index - 1);
-            let bit = if (mask & self.buffer) == 0 { &0 } else { &1 };
-            value <<= 1;
-            value += bit;
+    /// Mirror the order of the bits
+    ///
+    /// Bit 0 is swapped with bit `len() - 1`, bit 1 with bit `len() - 2` and
+    /// so on, e.g. to hand a datagram received MSB first to a parser
+    /// expecting LSB first. Only the `len()` bits of the datagram are
+    /// touched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// assert_eq!(Datagram::new("0011_1011_0001"), Datagram::new("1000_1101_1100").reverse());
+    /// ```
+    pub fn reverse(&self) -> Datagram {
+        let buffer = if self.is_empty() {
+            0
+        } else {
+            self.buffer.reverse_bits() >> (Datagram::MAX_BITS as u32 - self.length_in_bit as u32)
+        };
+        Datagram {
+            length_in_bit: self.length_in_bit,
+            buffer,
         }
-        value
     }
 
     /// Create a new datagram from "binary" string
@@ -215,7 +597,7 @@ impl Datagram {
     /// # Arguments
     ///
     /// * `bit_repr` - Bit representation as string of zeros and ones.
-    ///                Arbitrary delimiter signs (for readability) are ignored
+    ///   Arbitrary delimiter signs (for readability) are ignored
     /// # Example
     ///
     /// ```rust
@@ -235,384 +617,3722 @@ impl Datagram {
         datagram
     }
 
-    fn into_big_endian_iter(self) -> DatagramBigEndianIterator {
-        DatagramBigEndianIterator {
-            datagram: self,
-            index: self.len(),
-        }
-    }
-
-    fn into_little_endian_iter(self) -> DatagramLittleEndianIterator {
-        DatagramLittleEndianIterator {
-            datagram: self,
-            index: 0,
-        }
-    }
-}
-
-impl Index<u8> for Datagram {
-    type Output = u128;
-
-    /// Access the n-th element via index
+    /// Create a datagram directly from its buffer and length
+    ///
+    /// Bit 0 of the buffer is the last bit sent in big endian order, i.e.
+    /// the datagram equals the one created by [`Datagram::new`] with the
+    /// binary representation of `buffer` padded to `length_in_bit` digits.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - The bits, all bits at index `length_in_bit` and above must be zero
+    /// * `length_in_bit` - The number of bits, at most 128
     ///
     /// # Panics
     ///
-    /// * if the index is out of range
+    /// In debug builds, if the length exceeds 128 or unused high bits are set
     ///
     /// # Example
     ///
     /// ```rust
     /// use manchester_code::Datagram;
     ///
-    /// let datagram = Datagram::new("0-111_10101_00001111");
-    /// assert_eq!(1, datagram[0]);
-    /// assert_eq!(0, datagram[5]);
+    /// const POWER: Datagram = Datagram::from_raw(0b11_0000_0000_1100, 14);
+    /// assert_eq!(Datagram::new("11_0000_0000_1100"), POWER);
     /// ```
-    fn index(&self, index: u8) -> &Self::Output {
-        if index >= self.length_in_bit {
-            panic!("Wrong Index")
-        }
-        let mask: u128 = 1 << index;
-        if mask & self.buffer == 0 {
-            &0
-        } else {
-            &1
+    pub const fn from_raw(buffer: u128, length_in_bit: u8) -> Self {
+        debug_assert!(
+            length_in_bit <= Datagram::MAX_BITS,
+            "A datagram holds at most 128 bits"
+        );
+        debug_assert!(
+            length_in_bit >= Datagram::MAX_BITS || buffer >> length_in_bit == 0,
+            "Bits beyond the datagram length are set"
+        );
+        Datagram {
+            length_in_bit,
+            buffer,
         }
     }
-}
 
-impl PartialEq for Datagram {
-    fn eq(&self, other: &Self) -> bool {
-        self.buffer == other.buffer && self.length_in_bit == other.length_in_bit
+    /// Create a new datagram from a "binary" string with strict checking
+    ///
+    /// Unlike [`Datagram::new`] only `-`, `_` and whitespace are accepted as
+    /// delimiters.
+    ///
+    /// # Arguments
+    ///
+    /// * `bit_repr` - Bit representation as string of zeros and ones.
+    ///
+    /// # Returns
+    ///
+    /// * ParseError - if an illegal character or more than 128 bits are found
+    /// * the datagram otherwise
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{Datagram, ParseError};
+    ///
+    /// assert_eq!(Ok(Datagram::new("0111_1010")), Datagram::parse("0111 1010"));
+    /// assert_eq!(
+    ///     Err(ParseError::IllegalCharacter { position: 1, character: 'x' }),
+    ///     Datagram::parse("0x11")
+    /// );
+    /// ```
+    pub fn parse(bit_repr: &str) -> Result<Self, ParseError> {
+        let mut datagram = Datagram::default();
+        for (position, character) in bit_repr.char_indices() {
+            match character {
+                '0' | '1' => datagram
+                    .add_bit(character == '1', BitOrder::BigEndian)
+                    .map_err(|_| ParseError::TooManyBits)?,
+                '-' | '_' => (),
+                c if c.is_whitespace() => (),
+                _ => {
+                    return Err(ParseError::IllegalCharacter {
+                        position,
+                        character,
+                    })
+                }
+            }
+        }
+        Ok(datagram)
     }
-}
-
-impl Eq for Datagram {}
 
-impl Format for Datagram {
-    fn format(&self, f: defmt::Formatter) {
-        for index in 0..self.length_in_bit {
-            if 0 == index % 4 {
-                defmt::write!(f, "-");
-            }
-            if 1 == self[self.length_in_bit - 1 - index] {
-                defmt::write!(f, "1");
-            } else {
-                defmt::write!(f, "0");
+    /// Create a datagram from a byte payload
+    ///
+    /// The bytes are transmitted in the order of the slice. Each byte is
+    /// transmitted MSB first for [`BitOrder::BigEndian`] and LSB first for
+    /// [`BitOrder::LittleEndian`], i.e. the datagram is meant to be sent by
+    /// an iterator of the same bit order.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The payload, only the first 16 bytes are considered
+    /// * `bit_order` - The order of the bits within a byte
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{BitOrder, Datagram};
+    ///
+    /// let datagram = Datagram::from_bytes(&[0xa5, 0x0f], BitOrder::BigEndian);
+    /// assert_eq!(Datagram::new("1010_0101_0000_1111"), datagram);
+    /// ```
+    pub fn from_bytes(bytes: &[u8], bit_order: BitOrder) -> Datagram {
+        let mut datagram = Datagram::default();
+        for byte in bytes.iter().take(16) {
+            for index in 0..8 {
+                let shift = match bit_order {
+                    BitOrder::BigEndian => 7 - index,
+                    BitOrder::LittleEndian => index,
+                };
+                // 16 bytes fit into the capacity
+                let _ = datagram.add_bit(byte & (1 << shift) != 0, bit_order);
             }
         }
+        datagram
     }
-}
-
-#[derive(Debug)]
-pub struct DatagramBigEndianIterator {
-    datagram: Datagram,
-    index: u8,
-}
 
-impl Iterator for DatagramBigEndianIterator {
+    /// Write the bits of the datagram into a byte buffer
+    ///
+    /// The inverse of [`Datagram::from_bytes`]: the bits are written in
+    /// transmission order of `bit_order`, MSB first within each byte for
+    /// [`BitOrder::BigEndian`] and LSB first for [`BitOrder::LittleEndian`].
+    /// The last byte is padded with zeros if the length is not a multiple
+    /// of 8.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - The buffer to write to, bytes that do not fit are dropped
+    /// * `bit_order` - The order of the bits within a byte
+    ///
+    /// # Returns
+    ///
+    /// the number of bytes written
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{BitOrder, Datagram};
+    ///
+    /// let mut bytes = [0; 4];
+    /// let datagram = Datagram::new("1010_0101_0011");
+    /// assert_eq!(2, datagram.to_bytes(&mut bytes, BitOrder::BigEndian));
+    /// assert_eq!([0xa5, 0x30], bytes[..2]);
+    ///
+    /// let datagram = Datagram::from_bytes(&[0xa5, 0x0f], BitOrder::LittleEndian);
+    /// assert_eq!(2, datagram.to_bytes(&mut bytes, BitOrder::LittleEndian));
+    /// assert_eq!([0xa5, 0x0f], bytes[..2]);
+    /// ```
+    pub fn to_bytes(&self, out: &mut [u8], bit_order: BitOrder) -> usize {
+        let count = ((self.length_in_bit as usize + 7) / 8).min(out.len());
+        out[..count].fill(0);
+        for (position, bit) in DatagramEitherIterator::new(*self, bit_order).enumerate() {
+            if position / 8 >= count {
+                break;
+            }
+            if bit {
+                out[position / 8] |= match bit_order {
+                    BitOrder::BigEndian => 0x80 >> (position % 8),
+                    BitOrder::LittleEndian => 1 << (position % 8),
+                };
+            }
+        }
+        count
+    }
+
+    /// Create a datagram of a numeric value with the minimal length to hold it
+    ///
+    /// The length is the position of the most significant one bit plus one,
+    /// i.e. `128 - value.leading_zeros()`. The value zero is represented by a
+    /// datagram holding a single zero bit (length 1), so that it still can be
+    /// transmitted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// assert_eq!(Datagram::new("1011"), Datagram::from_value_min(0b1011));
+    /// assert_eq!(Datagram::new("0"), Datagram::from_value_min(0));
+    /// ```
+    pub const fn from_value_min(value: u128) -> Self {
+        let length_in_bit = (128 - value.leading_zeros()) as u8;
+        Datagram {
+            length_in_bit: if length_in_bit == 0 { 1 } else { length_in_bit },
+            buffer: value,
+        }
+    }
+
+    /// Create a datagram of alternating bits, e.g. `0101...`
+    ///
+    /// Useful as known test pattern during hardware bring-up.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - Number of bits, it is limited to the capacity of a datagram
+    /// * `start` - Value of the first transmitted (most significant) bit
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// assert_eq!(Datagram::new("1010"), Datagram::alternating(4, true));
+    /// ```
+    pub fn alternating(len: u8, start: bool) -> Self {
+        let mut datagram = Datagram::default();
+        let mut bit = start;
+        for _ in 0..len {
+            if datagram.add_bit(bit, BitOrder::BigEndian).is_err() {
+                break;
+            }
+            bit = !bit;
+        }
+        datagram
+    }
+
+    /// Create a datagram of a byte that is repeated count times
+    ///
+    /// Each byte is transmitted MSB first.
+    ///
+    /// # Returns
+    ///
+    /// * None - if the resulting datagram exceeds the capacity of a datagram
+    /// * Some(datagram) - the datagram of `8 * count` bits
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// assert_eq!(
+    ///     Some(Datagram::new("1010_0101_1010_0101")),
+    ///     Datagram::repeated_byte(0xa5, 2)
+    /// );
+    /// ```
+    pub fn repeated_byte(byte: u8, count: u8) -> Option<Self> {
+        let mut datagram = Datagram::default();
+        for _ in 0..count {
+            for index in (0..8).rev() {
+                datagram
+                    .add_bit(byte & (1 << index) != 0, BitOrder::BigEndian)
+                    .ok()?;
+            }
+        }
+        Some(datagram)
+    }
+
+    /// Append the complement of a byte of the datagram
+    ///
+    /// Protocols like NEC transmit a value followed by its inverse as
+    /// integrity check. The appended byte is transmitted last, i.e. it
+    /// occupies the indices 0 to 7 of the resulting datagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - index of the lowest bit of the byte to invert as used by
+    ///   [`Datagram::extract_data`]
+    ///
+    /// # Returns
+    ///
+    /// * None - if the byte is out of range or the capacity would be exceeded
+    /// * Some(datagram) - the datagram extended by the inverted byte
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// assert_eq!(
+    ///     Some(Datagram::new("0000_0100_1111_1011")),
+    ///     Datagram::new("0000_0100").with_inverted_byte_appended(0)
+    /// );
+    /// ```
+    pub fn with_inverted_byte_appended(&self, offset: u8) -> Option<Datagram> {
+        if offset.checked_add(8)? > self.length_in_bit {
+            return None;
+        }
+        let inverted = !(self.extract_data(offset, offset + 8) as u8);
+        let mut datagram = *self;
+        for index in (0..8).rev() {
+            datagram
+                .add_bit(inverted & (1 << index) != 0, BitOrder::BigEndian)
+                .ok()?;
+        }
+        Some(datagram)
+    }
+
+    /// Number of samples a sampled (or rendered) transmission of the datagram takes
+    ///
+    /// Handy to size sample buffers exactly.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples_per_half_bit` - Oversampling of each half bit
+    /// * `lead` - Number of idle samples before the datagram
+    /// * `trail` - Number of idle samples after the datagram
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// let datagram = Datagram::new("1011");
+    /// assert_eq!(2 + 4 * 2 * 3 + 9, datagram.sample_count(3, 2, 9));
+    /// ```
+    pub fn sample_count(&self, samples_per_half_bit: u8, lead: u8, trail: u8) -> usize {
+        lead as usize
+            + half_bit_count(self.length_in_bit) * samples_per_half_bit as usize
+            + trail as usize
+    }
+
+    /// Compare two datagrams ignoring zero padding at the end
+    ///
+    /// Trailing bits are the ones at the lowest indices (see [`Index`]),
+    /// i.e. the bits transmitted last in big endian order. The datagrams
+    /// are compared up to the shorter length, the extra trailing bits of the
+    /// longer datagram must all be zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// let datagram = Datagram::new("1011");
+    /// assert!(datagram.eq_ignoring_trailing_pad(&Datagram::new("1011_0000")));
+    /// assert!(!datagram.eq_ignoring_trailing_pad(&Datagram::new("1011_0001")));
+    /// ```
+    pub fn eq_ignoring_trailing_pad(&self, other: &Datagram) -> bool {
+        let (shorter, longer) = if self.length_in_bit <= other.length_in_bit {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let pad = longer.length_in_bit - shorter.length_in_bit;
+        if pad == 0 || shorter.is_empty() {
+            return shorter.buffer == longer.buffer;
+        }
+        let pad_mask = (1_u128 << pad) - 1;
+        longer.buffer & pad_mask == 0 && longer.buffer >> pad == shorter.buffer
+    }
+
+    /// XOR successive chunks of the datagram
+    ///
+    /// The chunks are aligned to index zero, i.e. if the length is not a
+    /// multiple of `chunk_bits` the leading (first transmitted in big endian
+    /// order) chunk is the partial one and is zero extended.
+    /// A chunk width of zero yields zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// let datagram = Datagram::new("1010_0101_1111");
+    /// assert_eq!(0b0000, datagram.xor_checksum(4));
+    /// assert_eq!(0b0101_0101, datagram.xor_checksum(8));
+    /// ```
+    pub fn xor_checksum(&self, chunk_bits: u8) -> u128 {
+        if chunk_bits == 0 {
+            return 0;
+        }
+        if chunk_bits >= Datagram::MAX_BITS {
+            return self.buffer;
+        }
+        let mask = (1_u128 << chunk_bits) - 1;
+        let mut remaining = self.buffer;
+        let mut checksum = 0;
+        while remaining != 0 {
+            checksum ^= remaining & mask;
+            remaining >>= chunk_bits;
+        }
+        checksum
+    }
+
+    /// Compute a CRC-8 over a range of bits
+    ///
+    /// The bits are processed in big endian order, i.e. from index `to - 1`
+    /// down to `from`, with an initial value of zero and without reflection
+    /// or final XOR (e.g. CRC-8/SMBUS for `poly = 0x07`).
+    ///
+    /// # Arguments
+    ///
+    /// * `poly` - the generator polynomial without the leading `x^8` term
+    /// * `from` - start index (included)
+    /// * `to` - end index (not included)
+    ///
+    /// # Panics
+    ///  if 0 <= from <= to <= len() is violated
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{BitOrder, Datagram};
+    ///
+    /// let datagram = Datagram::from_bytes(b"123456789", BitOrder::BigEndian);
+    /// assert_eq!(0xf4, datagram.crc8(0x07, 0, 72));
+    /// ```
+    pub fn crc8(&self, poly: u8, from: u8, to: u8) -> u8 {
+        if to > self.length_in_bit || from > to {
+            panic!("Bit range out of bounds");
+        }
+        let mut crc = 0_u8;
+        for index in (from..to).rev() {
+            let feedback = (crc >> 7) ^ (self.buffer >> index) as u8 & 1;
+            crc <<= 1;
+            if feedback == 1 {
+                crc ^= poly;
+            }
+        }
+        crc
+    }
+
+    /// Compute the even parity bit of a range of bits
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - start index (included)
+    /// * `to` - end index (not included)
+    ///
+    /// # Returns
+    ///
+    /// `true` if the number of set bits in the range is odd, i.e. the bit
+    /// that makes the number of set bits even together with the range
+    ///
+    /// # Panics
+    ///  if 0 <= from <= to <= len() is violated
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// assert!(Datagram::new("1011").parity(0, 4));
+    /// assert!(!Datagram::new("1011").parity(0, 2));
+    /// ```
+    pub fn parity(&self, from: u8, to: u8) -> bool {
+        if to > self.length_in_bit || from > to {
+            panic!("Bit range out of bounds");
+        }
+        let width = (to - from) as u32;
+        let mask = u128::MAX.checked_shr(128 - width).unwrap_or(0);
+        let bits = self.buffer.checked_shr(from as u32).unwrap_or(0) & mask;
+        bits.count_ones() % 2 == 1
+    }
+
+    /// Verify and remove a parity bit following every 8 data bits
+    ///
+    /// Each byte is transmitted (big endian) as 8 data bits followed by its
+    /// parity bit, i.e. the datagram consists of 9 bit groups with the
+    /// parity bit at the lowest index of each group.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(datagram)` - the data bits only, if all parity bits are correct
+    /// * `None` - on a parity failure or if the length is not a multiple of 9
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{Datagram, Parity};
+    ///
+    /// let datagram = Datagram::new("1010_0101_0");
+    /// assert_eq!(Some(Datagram::new("1010_0101")), datagram.strip_byte_parity(Parity::Even));
+    /// assert_eq!(None, datagram.strip_byte_parity(Parity::Odd));
+    /// ```
+    pub fn strip_byte_parity(&self, parity: Parity) -> Option<Datagram> {
+        if self.length_in_bit % 9 != 0 {
+            return None;
+        }
+        let mut stripped = Datagram::default();
+        for group in (0..self.length_in_bit / 9).rev() {
+            let bits = (self.buffer >> (group * 9)) & 0x1ff;
+            let odd = bits.count_ones() % 2 == 1;
+            if odd != (parity == Parity::Odd) {
+                return None;
+            }
+            stripped.buffer = (stripped.buffer << 8) | (bits >> 1);
+            stripped.length_in_bit += 8;
+        }
+        Some(stripped)
+    }
+
+    /// Convert a binary coded datagram into its Gray code representation
+    ///
+    /// The whole valid length is converted, the length is kept.
+    /// Combine with [`Datagram::extract_data`] to convert a single field.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// assert_eq!(Datagram::new("0111"), Datagram::new("0101").binary_to_gray());
+    /// ```
+    pub fn binary_to_gray(&self) -> Datagram {
+        Datagram {
+            length_in_bit: self.length_in_bit,
+            buffer: self.buffer ^ (self.buffer >> 1),
+        }
+    }
+
+    /// Convert a Gray coded datagram into its binary representation
+    ///
+    /// This is the inverse of [`Datagram::binary_to_gray`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// assert_eq!(Datagram::new("0101"), Datagram::new("0111").gray_to_binary());
+    /// ```
+    pub fn gray_to_binary(&self) -> Datagram {
+        let mut buffer = self.buffer;
+        let mut shift = 1;
+        while shift < u128::BITS {
+            buffer ^= buffer >> shift;
+            shift <<= 1;
+        }
+        Datagram {
+            length_in_bit: self.length_in_bit,
+            buffer,
+        }
+    }
+
+    /// Render the datagram as hexadecimal nibbles
+    ///
+    /// The bits are grouped into nibbles MSB first (i.e. in the same order
+    /// as the `Format` output) and rendered as `0-9a-f`.
+    /// If the length is not a multiple of four, the leading nibble is the
+    /// partial one: it holds the `len() % 4` most significant bits only,
+    /// i.e. a 14 bit datagram renders as 4 digits where the first one is
+    /// in the range `0-3`.
+    ///
+    /// Rendering stops if the capacity `N` of the string is exhausted.
+    /// 32 characters are always sufficient.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// let datagram = Datagram::new("11_0101_1111");
+    /// assert_eq!("35f", datagram.to_hex_string::<32>().as_str());
+    /// ```
+    pub fn to_hex_string<const N: usize>(&self) -> String<N> {
+        const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let mut hex = String::new();
+        let mut remaining = self.length_in_bit;
+        while remaining > 0 {
+            let width = match remaining % 4 {
+                0 => 4,
+                partial => partial,
+            };
+            remaining -= width;
+            let nibble = (self.buffer >> remaining) & ((1 << width) - 1);
+            if hex.push(char::from(HEX_DIGITS[nibble as usize])).is_err() {
+                break;
+            }
+        }
+        hex
+    }
+
+    /// Check that the datagram is well-formed under a given schema
+    ///
+    /// The checks are applied in the order length, fixed bits and field
+    /// ranges. The first violation found is reported.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{Datagram, Schema, ValidationError};
+    ///
+    /// let schema = Schema::new()
+    ///     .required_len(8)
+    ///     .fixed_bits(0b1100_0000, 0b1000_0000)
+    ///     .field_range(0, 4, 1, 9);
+    /// assert_eq!(Ok(()), Datagram::new("1000_0101").validate(&schema));
+    /// assert_eq!(
+    ///     Err(ValidationError::FieldOutOfRange { offset: 0 }),
+    ///     Datagram::new("1000_1101").validate(&schema)
+    /// );
+    /// ```
+    pub fn validate(&self, schema: &Schema) -> Result<(), ValidationError> {
+        if let Some(required_len) = schema.required_len {
+            if required_len != self.length_in_bit {
+                return Err(ValidationError::LengthMismatch);
+            }
+        }
+        if self.buffer & schema.fixed_mask != schema.fixed_value {
+            return Err(ValidationError::FixedBitsMismatch);
+        }
+        for (offset, width, min, max) in schema.field_ranges.iter().flatten() {
+            let in_range = offset
+                .checked_add(*width)
+                .and_then(|end| self.try_extract_data(*offset, end).ok())
+                .is_some_and(|value| (min..=max).contains(&&value));
+            if !in_range {
+                return Err(ValidationError::FieldOutOfRange { offset: *offset });
+            }
+        }
+        Ok(())
+    }
+
+    fn into_big_endian_iter(self) -> DatagramBigEndianIterator {
+        DatagramBigEndianIterator {
+            datagram: self,
+            index: self.len(),
+            back: 0,
+        }
+    }
+
+    fn into_little_endian_iter(self) -> DatagramLittleEndianIterator {
+        DatagramLittleEndianIterator {
+            datagram: self,
+            index: 0,
+            end: self.len(),
+        }
+    }
+
+    /// Iterate the bits in big endian order (highest index first)
+    ///
+    /// This is the order of transmission of an
+    /// `Encoder::<DatagramBigEndianIterator>`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// let datagram = Datagram::new("110");
+    /// let mut bits = datagram.iter_big_endian();
+    /// assert_eq!(Some(true), bits.next());
+    /// assert_eq!(Some(true), bits.next());
+    /// assert_eq!(Some(false), bits.next());
+    /// assert_eq!(None, bits.next());
+    /// ```
+    pub fn iter_big_endian(&self) -> DatagramBigEndianIterator {
+        self.into_big_endian_iter()
+    }
+
+    /// Iterate the bits in little endian order (index 0 first)
+    ///
+    /// This is the order of transmission of an
+    /// `Encoder::<DatagramLittleEndianIterator>`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// let datagram = Datagram::new("110");
+    /// let mut bits = datagram.iter_little_endian();
+    /// assert_eq!(Some(false), bits.next());
+    /// assert_eq!(Some(true), bits.next());
+    /// assert_eq!(Some(true), bits.next());
+    /// assert_eq!(None, bits.next());
+    /// ```
+    pub fn iter_little_endian(&self) -> DatagramLittleEndianIterator {
+        self.into_little_endian_iter()
+    }
+}
+
+impl Index<u8> for Datagram {
+    type Output = u128;
+
+    /// Access the n-th element via index
+    ///
+    /// # Panics
+    ///
+    /// * if the index is out of range
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::Datagram;
+    ///
+    /// let datagram = Datagram::new("0-111_10101_00001111");
+    /// assert_eq!(1, datagram[0]);
+    /// assert_eq!(0, datagram[5]);
+    /// ```
+    fn index(&self, index: u8) -> &Self::Output {
+        match self.get(index) {
+            Some(0) => &0,
+            Some(_) => &1,
+            None => panic!("Wrong Index"),
+        }
+    }
+}
+
+/// Iterate the bits in big endian order, i.e. in the order of transmission
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::Datagram;
+///
+/// let mut bits = Datagram::new("10").into_iter();
+/// assert_eq!(Some(true), bits.next());
+/// assert_eq!(Some(false), bits.next());
+/// assert_eq!(None, bits.next());
+/// ```
+impl IntoIterator for Datagram {
+    type Item = bool;
+    type IntoIter = DatagramBigEndianIterator;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_big_endian_iter()
+    }
+}
+
+/// Collect bits in big endian order, i.e. in the order of transmission
+///
+/// # Panics
+///
+/// * if more than 128 bits are collected
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::Datagram;
+///
+/// let datagram: Datagram = [true, false, true].iter().copied().collect();
+/// assert_eq!(Datagram::new("101"), datagram);
+/// ```
+impl core::iter::FromIterator<bool> for Datagram {
+    fn from_iter<T: IntoIterator<Item = bool>>(bits: T) -> Self {
+        let mut datagram = Datagram::default();
+        for bit in bits {
+            if datagram.add_bit(bit, BitOrder::BigEndian).is_err() {
+                panic!("A datagram holds at most 128 bits");
+            }
+        }
+        datagram
+    }
+}
+
+/// Parse a datagram like [`Datagram::parse`]
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{Datagram, ParseError};
+///
+/// let datagram: Datagram = "0101_1100".parse().unwrap();
+/// assert_eq!(Datagram::new("0101_1100"), datagram);
+/// assert_eq!(Err(ParseError::TooManyBits), "1".repeat(129).parse::<Datagram>());
+/// ```
+impl core::str::FromStr for Datagram {
+    type Err = ParseError;
+
+    fn from_str(bit_repr: &str) -> Result<Self, Self::Err> {
+        Datagram::parse(bit_repr)
+    }
+}
+
+/// Convert a string like [`Datagram::parse`]
+impl core::convert::TryFrom<&str> for Datagram {
+    type Error = ParseError;
+
+    fn try_from(bit_repr: &str) -> Result<Self, Self::Error> {
+        Datagram::parse(bit_repr)
+    }
+}
+
+impl PartialEq for Datagram {
+    fn eq(&self, other: &Self) -> bool {
+        self.buffer == other.buffer && self.length_in_bit == other.length_in_bit
+    }
+}
+
+impl Eq for Datagram {}
+
+/// Datagrams are ordered by their length first and by their bits second
+///
+/// Two datagrams of different length are never equal, even if their
+/// buffers hold the same numeric value, e.g. `"01" < "001"`.
+impl Ord for Datagram {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.length_in_bit
+            .cmp(&other.length_in_bit)
+            .then(self.buffer.cmp(&other.buffer))
+    }
+}
+
+impl PartialOrd for Datagram {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for Datagram {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.length_in_bit.hash(state);
+        self.buffer.hash(state);
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl Format for Datagram {
+    fn format(&self, f: defmt::Formatter) {
+        for index in 0..self.length_in_bit {
+            if 0 == index % 4 {
+                defmt::write!(f, "-");
+            }
+            defmt::write!(f, "{=u8}", self[self.length_in_bit - 1 - index] as u8);
+        }
+    }
+}
+
+/// Render the bits like the `defmt::Format` implementation (MSB first, grouped by 4)
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::Datagram;
+///
+/// let datagram = Datagram::new("1010_01");
+/// assert_eq!("-1010-01", format!("{}", datagram));
+/// assert_eq!("-1010-01", format!("{:b}", datagram));
+/// ```
+impl core::fmt::Display for Datagram {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for index in 0..self.length_in_bit {
+            if 0 == index % 4 {
+                f.write_str("-")?;
+            }
+            if 1 == self[self.length_in_bit - 1 - index] {
+                f.write_str("1")?;
+            } else {
+                f.write_str("0")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Binary for Datagram {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(self, f)
+    }
+}
+
+/// Maximum number of field ranges a [`Schema`] can hold
+pub const MAX_SCHEMA_FIELD_RANGES: usize = 8;
+
+/// Declaration of a well-formed datagram of a fixed protocol
+///
+/// A schema packages the usual per-frame sanity checks and is applied by
+/// [`Datagram::validate`]. Bits are indexed like for [`Index`], i.e. bit
+/// zero is the one transmitted last in big endian order.
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::Schema;
+///
+/// // RC5: 14 bits, both start bits set, command in range 0..=63
+/// const RC5: Schema = Schema::new()
+///     .required_len(14)
+///     .fixed_bits(0b11 << 12, 0b11 << 12)
+///     .field_range(0, 6, 0, 63);
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Schema {
+    required_len: Option<u8>,
+    fixed_mask: u128,
+    fixed_value: u128,
+    // offset, width, min, max
+    field_ranges: [Option<(u8, u8, u128, u128)>; MAX_SCHEMA_FIELD_RANGES],
+}
+
+impl Schema {
+    /// Create a schema every datagram conforms to
+    pub const fn new() -> Self {
+        Schema {
+            required_len: None,
+            fixed_mask: 0,
+            fixed_value: 0,
+            field_ranges: [None; MAX_SCHEMA_FIELD_RANGES],
+        }
+    }
+
+    /// Require the datagram to have exactly the length passed along
+    pub const fn required_len(mut self, len: u8) -> Self {
+        self.required_len = Some(len);
+        self
+    }
+
+    /// Require the bits selected by `mask` to be equal to `value`
+    ///
+    /// Repeated calls accumulate, a later call overrides the value of bits
+    /// selected by an earlier call.
+    ///
+    /// # Arguments
+    ///
+    /// * `mask` - the bits to be checked
+    /// * `value` - the expected values of the checked bits
+    pub const fn fixed_bits(mut self, mask: u128, value: u128) -> Self {
+        self.fixed_value = (self.fixed_value & !mask) | (value & mask);
+        self.fixed_mask |= mask;
+        self
+    }
+
+    /// Require a field to be within a value range
+    ///
+    /// The field is extracted like by [`Datagram::extract_data`] with
+    /// `min = offset` and `max = offset + width`. A field not covered by
+    /// the datagram is out of range.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - index of the least significant bit of the field
+    /// * `width` - number of bits of the field
+    /// * `min` - the smallest valid value (included)
+    /// * `max` - the largest valid value (included)
+    ///
+    /// # Panics
+    ///
+    /// * if `width` is zero
+    /// * if more than [`MAX_SCHEMA_FIELD_RANGES`] field ranges are declared
+    pub const fn field_range(mut self, offset: u8, width: u8, min: u128, max: u128) -> Self {
+        assert!(width > 0, "A field has at least one bit");
+        let mut index = 0;
+        while index < MAX_SCHEMA_FIELD_RANGES {
+            if self.field_ranges[index].is_none() {
+                self.field_ranges[index] = Some((offset, width, min, max));
+                return self;
+            }
+            index += 1;
+        }
+        panic!("Too many field ranges");
+    }
+}
+
+/// Reasons why a datagram does not conform to a [`Schema`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The datagram does not have the required length
+    LengthMismatch,
+    /// At least one of the fixed bits has the wrong value
+    FixedBitsMismatch,
+    /// The field starting at `offset` is out of its range
+    FieldOutOfRange { offset: u8 },
+}
+
+#[derive(Debug)]
+pub struct DatagramBigEndianIterator {
+    datagram: Datagram,
+    // one above the next index from the front
+    index: u8,
+    // the next index from the back
+    back: u8,
+}
+
+impl Iterator for DatagramBigEndianIterator {
+    type Item = bool;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.back < self.index {
+            self.index -= 1;
+            Some(1 == self.datagram[self.index])
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.index - self.back) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl DoubleEndedIterator for DatagramBigEndianIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back < self.index {
+            self.back += 1;
+            Some(1 == self.datagram[self.back - 1])
+        } else {
+            None
+        }
+    }
+}
+
+impl ExactSizeIterator for DatagramBigEndianIterator {}
+
+#[derive(Debug)]
+pub struct DatagramLittleEndianIterator {
+    datagram: Datagram,
+    // the next index from the front
+    index: u8,
+    // one above the next index from the back
+    end: u8,
+}
+
+impl Iterator for DatagramLittleEndianIterator {
+    type Item = bool;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.end > self.index {
+            self.index += 1;
+            Some(1 == self.datagram[self.index - 1])
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.end - self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl DoubleEndedIterator for DatagramLittleEndianIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.end > self.index {
+            self.end -= 1;
+            Some(1 == self.datagram[self.end])
+        } else {
+            None
+        }
+    }
+}
+
+impl ExactSizeIterator for DatagramLittleEndianIterator {}
+
+/// Iterator over the bits of a datagram in a bit order chosen at runtime
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{BitOrder, Datagram, DatagramEitherIterator};
+///
+/// let datagram = Datagram::new("10");
+/// let mut iter = DatagramEitherIterator::new(datagram, BitOrder::LittleEndian);
+/// assert_eq!(Some(false), iter.next());
+/// assert_eq!(Some(true), iter.next());
+/// assert_eq!(None, iter.next());
+/// ```
+#[derive(Debug)]
+pub enum DatagramEitherIterator {
+    BigEndian(DatagramBigEndianIterator),
+    LittleEndian(DatagramLittleEndianIterator),
+}
+
+impl DatagramEitherIterator {
+    /// Create an iterator over the bits of a datagram
+    ///
+    /// # Arguments
+    ///
+    /// * `datagram` - The datagram to iterate over
+    /// * `bit_order` - The order the bits are transmitted in
+    pub fn new(datagram: Datagram, bit_order: BitOrder) -> Self {
+        match bit_order {
+            BitOrder::BigEndian => {
+                DatagramEitherIterator::BigEndian(datagram.into_big_endian_iter())
+            }
+            BitOrder::LittleEndian => {
+                DatagramEitherIterator::LittleEndian(datagram.into_little_endian_iter())
+            }
+        }
+    }
+}
+
+impl Iterator for DatagramEitherIterator {
+    type Item = bool;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            DatagramEitherIterator::BigEndian(iter) => iter.next(),
+            DatagramEitherIterator::LittleEndian(iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            DatagramEitherIterator::BigEndian(iter) => iter.size_hint(),
+            DatagramEitherIterator::LittleEndian(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl DoubleEndedIterator for DatagramEitherIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            DatagramEitherIterator::BigEndian(iter) => iter.next_back(),
+            DatagramEitherIterator::LittleEndian(iter) => iter.next_back(),
+        }
+    }
+}
+
+impl ExactSizeIterator for DatagramEitherIterator {}
+
+/// Iterator over the bits of a datagram in the order of transmission
+pub trait DatagramIterator: Iterator<Item = bool> {
+    /// Create the iterator for the datagram passed along
+    fn from_datagram(datagram: Datagram) -> Self;
+}
+
+impl DatagramIterator for DatagramBigEndianIterator {
+    fn from_datagram(datagram: Datagram) -> Self {
+        datagram.into_big_endian_iter()
+    }
+}
+
+impl DatagramIterator for DatagramLittleEndianIterator {
+    fn from_datagram(datagram: Datagram) -> Self {
+        datagram.into_little_endian_iter()
+    }
+}
+
+/// Number of half bits a Manchester encoded transmission of `bit_len` bits takes
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::half_bit_count;
+///
+/// assert_eq!(28, half_bit_count(14));
+/// ```
+pub const fn half_bit_count(bit_len: u8) -> usize {
+    bit_len as usize * 2
+}
+
+/// Duration in µs of a Manchester encoded transmission of `bit_len` bits
+///
+/// # Arguments
+///
+/// * `bit_len` - number of bits transmitted
+/// * `half_bit_us` - duration of a half bit in µs, e.g. 889 µs for RC5
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::frame_duration_us;
+///
+/// assert_eq!(24_892, frame_duration_us(14, 889));
+/// ```
+pub const fn frame_duration_us(bit_len: u8, half_bit_us: u32) -> u32 {
+    (half_bit_count(bit_len) as u32).saturating_mul(half_bit_us)
+}
+
+/// Encodes a datagram to Manchester code
+///
+/// The encoder turns into an iterator.
+/// the encoding happens by calling the iterator.
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{
+///     Datagram,
+///     DatagramBigEndianIterator,
+///     Encoder};
+///
+/// let mut encoder = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("01"));
+/// assert_eq!(Some(true), encoder.next());
+/// assert_eq!(Some(false), encoder.next());
+/// assert_eq!(Some(false), encoder.next());
+/// assert_eq!(Some(true), encoder.next());
+/// assert_eq!(None, encoder.next());
+/// ```
+
+#[derive(Debug)]
+pub struct Encoder<I> {
+    datagram_iter: I,
+    first_half_bit: bool,
+    last_value: Option<bool>,
+    // idle half bits appended after the datagram
+    padding: usize,
+    convention: Convention,
+}
+
+impl<I: Iterator<Item = bool>> Encoder<I> {
+    fn from_bits(mut datagram_iter: I) -> Self {
+        let last_value = datagram_iter.next();
+        Encoder {
+            datagram_iter,
+            first_half_bit: true,
+            last_value,
+            padding: 0,
+            convention: Convention::GeThomas,
+        }
+    }
+}
+
+impl<I: ExactSizeIterator<Item = bool>> Encoder<I> {
+    /// Number of half bits not yet yielded, including the padding
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{Datagram, DatagramBigEndianIterator, Encoder};
+    ///
+    /// let mut encoder = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("011"));
+    /// assert_eq!(6, encoder.remaining_half_bits());
+    /// encoder.next();
+    /// assert_eq!(5, encoder.remaining_half_bits());
+    /// ```
+    pub fn remaining_half_bits(&self) -> usize {
+        let current_bit = match self.last_value {
+            Some(_) if self.first_half_bit => 2,
+            Some(_) => 1,
+            None => 0,
+        };
+        current_bit + 2 * self.datagram_iter.len() + self.padding
+    }
+}
+
+impl Encoder<DatagramEitherIterator> {
+    /// Create a new Encoder with a bit order chosen at runtime
+    ///
+    /// # Arguments
+    ///
+    /// * `datagram` - the datagram to be encoded
+    /// * `bit_order` - the order the bits are transmitted in
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{BitOrder, Datagram, DatagramLittleEndianIterator, Encoder};
+    ///
+    /// let datagram = Datagram::new("01");
+    /// assert!(Encoder::with_bit_order(datagram, BitOrder::LittleEndian)
+    ///     .eq(Encoder::<DatagramLittleEndianIterator>::new(datagram)));
+    /// ```
+    pub fn with_bit_order(d: Datagram, bit_order: BitOrder) -> Self {
+        Self::from_bits(DatagramEitherIterator::new(d, bit_order))
+    }
+}
+
+impl<I: DatagramIterator> Encoder<I> {
+    /// Create a new Encoder ready to encode the datagram passed along
+    ///
+    /// # Arguments
+    ///
+    /// * `datagram` - the datagram to be encoded
+    pub fn new(d: Datagram) -> Self {
+        Self::from_bits(I::from_datagram(d))
+    }
+
+    /// Encode with another mapping of the mid-bit edge direction to the bit value
+    ///
+    /// The half bits of the encoder are `true` for an active line (carrier
+    /// on). With the default [`Convention::GeThomas`] a one is sent as
+    /// inactive-then-active half bits, with [`Convention::Ieee8023`] as
+    /// active-then-inactive half bits.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{Convention, Datagram, DatagramBigEndianIterator, Encoder};
+    ///
+    /// let encoder = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("1"))
+    ///     .with_convention(Convention::Ieee8023);
+    /// assert_eq!([true, false], encoder.collect::<heapless::Vec<bool, 2>>()[..]);
+    /// ```
+    pub fn with_convention(mut self, convention: Convention) -> Self {
+        self.convention = convention;
+        self
+    }
+
+    /// Create a new Encoder that pads the datagram to a fixed number of half bits
+    ///
+    /// Idle (`false`) half bits are appended after the datagram such that
+    /// every frame occupies the same time slot regardless of its length.
+    ///
+    /// # Arguments
+    ///
+    /// * `datagram` - the datagram to be encoded
+    /// * `total_half_bits` - the number of half bits the encoder yields
+    ///
+    /// # Returns
+    ///
+    /// * `Some(encoder)` - the padding encoder
+    /// * `None` - if the datagram alone takes more than `total_half_bits`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{Datagram, DatagramBigEndianIterator, Encoder};
+    ///
+    /// let encoder = Encoder::<DatagramBigEndianIterator>::padded_to(Datagram::new("01"), 6);
+    /// assert_eq!(6, encoder.unwrap().count());
+    /// ```
+    pub fn padded_to(d: Datagram, total_half_bits: usize) -> Option<Self> {
+        let padding = total_half_bits.checked_sub(half_bit_count(d.len()))?;
+        let mut encoder = Self::new(d);
+        encoder.padding = padding;
+        Some(encoder)
+    }
+
+    /// Reuse the encoder for a new datagram
+    ///
+    /// Encoding restarts with the first half bit of the datagram passed
+    /// along, any not yet encoded half bits of the previous datagram are
+    /// dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `datagram` - the datagram to be encoded
+    pub fn load(&mut self, d: Datagram) {
+        *self = Self::new(d).with_convention(self.convention);
+    }
+
+    /// Render all remaining half bits into a buffer
+    ///
+    /// E.g. to prepare a buffer a DMA transfers to the PWM (see [`DmaEmitter`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `half_bits` - the buffer the half bits are written to
+    ///
+    /// # Returns
+    ///
+    /// * `Some(count)` - the number of half bits written
+    /// * `None` - if the buffer is too small, its content is undefined then
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{Datagram, DatagramBigEndianIterator, Encoder};
+    ///
+    /// let mut half_bits = [false; 8];
+    /// let encoder = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("01"));
+    /// assert_eq!(Some(4), encoder.render_into(&mut half_bits));
+    /// assert_eq!([true, false, false, true], half_bits[..4]);
+    /// ```
+    pub fn render_into(self, half_bits: &mut [bool]) -> Option<usize> {
+        let mut count = 0;
+        for half_bit in self {
+            *half_bits.get_mut(count)? = half_bit;
+            count += 1;
+        }
+        Some(count)
+    }
+}
+
+impl<I: Iterator<Item = bool>> Iterator for Encoder<I> {
+    type Item = bool;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.last_value {
+            Some(bit) => {
+                let bit = bit ^ (self.convention == Convention::Ieee8023);
+                if self.first_half_bit {
+                    self.first_half_bit = false;
+                    Some(!bit)
+                } else {
+                    self.first_half_bit = true;
+                    self.last_value = self.datagram_iter.next();
+                    Some(bit)
+                }
+            }
+            None if self.padding > 0 => {
+                self.padding -= 1;
+                Some(false)
+            }
+            None => None,
+        }
+    }
+}
+
+/// Gate a carrier with half bits to drive an infrared LED by bit-banging
+///
+/// A tick is half a carrier period, i.e. the pin toggles every tick while
+/// the half bit is active (`true`) and stays low while it is inactive.
+/// The carrier starts high at the beginning of every half bit.
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{CarrierGate, Datagram, DatagramBigEndianIterator, Encoder};
+///
+/// // 36 kHz carrier at a half bit time of 889 µs
+/// const TICKS_PER_HALF_BIT: u8 = 64;
+/// let encoder = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("1"));
+/// let levels = CarrierGate::new(encoder, TICKS_PER_HALF_BIT);
+/// assert_eq!(32, levels.filter(|level| *level).count());
+/// ```
+#[derive(Debug)]
+pub struct CarrierGate<I> {
+    half_bits: I,
+    ticks_per_half_bit: u8,
+    tick: u8,
+    active: bool,
+}
+
+impl<I: Iterator<Item = bool>> CarrierGate<I> {
+    /// Create a carrier gate
+    ///
+    /// # Arguments
+    ///
+    /// * `half_bits` - The half bits to transmit, e.g. an [`Encoder`]
+    /// * `ticks_per_half_bit` - The number of ticks (carrier half periods) per half bit
+    ///
+    /// # Panics
+    ///
+    /// If `ticks_per_half_bit` is zero
+    pub const fn new(half_bits: I, ticks_per_half_bit: u8) -> Self {
+        assert!(ticks_per_half_bit > 0, "At least one tick per half bit");
+        CarrierGate {
+            half_bits,
+            ticks_per_half_bit,
+            tick: 0,
+            active: false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = bool>> Iterator for CarrierGate<I> {
     type Item = bool;
+
+    /// The pin level for the next tick
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.tick == 0 {
+            self.active = self.half_bits.next()?;
+        }
+        let level = self.active && self.tick & 1 == 0;
+        self.tick += 1;
+        if self.tick == self.ticks_per_half_bit {
+            self.tick = 0;
+        }
+        Some(level)
+    }
+}
+
+/// Mapping of the direction of the mid-bit edge to the bit value
+///
+/// The direction is given on the line of an active low receiver, i.e.
+/// high is inactive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Convention {
+    /// A one is high-then-low, a zero is low-then-high (default, e.g. RC5)
+    GeThomas,
+    /// A one is low-then-high, a zero is high-then-low (e.g. Ethernet)
+    Ieee8023,
+}
+
+/// Activity level of the Pin where the infrared receiver is attached to.
+/// It is the opposite level the pin takes if no datagram is transmitted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ActivityLevel {
+    High,
+    Low,
+}
+
+/// A priori knowledge about the first expected bit of a telegram
+///
+/// It is needed for correct decoding if the datagram length is unknown
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SyncOnTurningEdge {
+    First,
+    Second,
+}
+
+/// Named combinations of [`ActivityLevel`] and [`SyncOnTurningEdge`]
+///
+/// Each variant states the first bit of a datagram and the idle level of
+/// the line, the corresponding pair is derived as documented for
+/// [`Decoder::new`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecodeMode {
+    /// First bit zero, the line is low when idle (high activity, first edge)
+    FirstBitZeroIdleLow,
+    /// First bit one, the line is low when idle (high activity, second edge)
+    FirstBitOneIdleLow,
+    /// First bit one, the line is high when idle (low activity, first edge)
+    FirstBitOneIdleHigh,
+    /// First bit zero, the line is high when idle (low activity, second edge)
+    FirstBitZeroIdleHigh,
+}
+
+impl DecodeMode {
+    /// The activity level and edge synchronization of the mode
+    pub const fn expand(self) -> (ActivityLevel, SyncOnTurningEdge) {
+        match self {
+            DecodeMode::FirstBitZeroIdleLow => (ActivityLevel::High, SyncOnTurningEdge::First),
+            DecodeMode::FirstBitOneIdleLow => (ActivityLevel::High, SyncOnTurningEdge::Second),
+            DecodeMode::FirstBitOneIdleHigh => (ActivityLevel::Low, SyncOnTurningEdge::First),
+            DecodeMode::FirstBitZeroIdleHigh => (ActivityLevel::Low, SyncOnTurningEdge::Second),
+        }
+    }
+}
+
+/// How bits are represented by the edges of the signal
+///
+/// For the differential variants a bit is given by the presence or absence
+/// of an edge between two clock edges, the direction of the edges does not
+/// matter. The clock edges are located like the mid-bit edges of
+/// [`Encoding::Standard`], so timing and synchronization are shared. The
+/// first clock edge of a datagram is the reference for the first bit, it
+/// does not carry a bit itself.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// The direction of the mid-bit edge determines the bit (default)
+    Standard,
+    /// An additional edge between two clock edges is a one (biphase mark)
+    DifferentialMark,
+    /// An additional edge between two clock edges is a zero (biphase space)
+    DifferentialSpace,
+}
+
+/// When a completed datagram is returned by the decoder
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EmitTiming {
+    /// On the call that detects the end of the datagram (default)
+    Immediate,
+    /// On the call following the detection of the end of the datagram, or
+    /// later if that call reports an error or an idle timeout
+    Deferred,
+}
+
+/// Convert analog samples, e.g. ADC readings, into levels with hysteresis
+///
+/// A sample above the high level is high, a sample below the low level is
+/// low. A sample in between keeps the previous level, so noise around the
+/// switching point of a slow comparator does not produce additional edges.
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::Threshold;
+///
+/// let threshold = Threshold::new(1000, 2000);
+/// assert!(threshold.level(2001, false));
+/// assert!(threshold.level(1500, true));
+/// assert!(!threshold.level(1500, false));
+/// assert!(!threshold.level(999, true));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Threshold {
+    low: u16,
+    high: u16,
+}
+
+impl Threshold {
+    /// Create a threshold
+    ///
+    /// # Arguments
+    ///
+    /// * `low` - Samples below are low
+    /// * `high` - Samples above are high
+    ///
+    /// # Panics
+    ///
+    /// If `low` is larger than `high`
+    pub const fn new(low: u16, high: u16) -> Self {
+        assert!(low <= high, "The low level must not exceed the high level");
+        Threshold { low, high }
+    }
+
+    /// Convert a sample into a level
+    ///
+    /// # Arguments
+    ///
+    /// * `sample` - The analog sample
+    /// * `previous` - The level of the previous sample
+    ///
+    /// # Returns
+    ///
+    /// The level of the sample, true equals high
+    pub const fn level(&self, sample: u16, previous: bool) -> bool {
+        if sample > self.high {
+            true
+        } else if sample < self.low {
+            false
+        } else {
+            previous
+        }
+    }
+}
+
+impl Default for Threshold {
+    /// The full range of a `u16` split in thirds
+    fn default() -> Self {
+        Threshold::new(u16::MAX / 3, u16::MAX / 3 * 2)
+    }
+}
+
+/// Decode a Manchester encoded stream of periodically taken samples into
+/// a datagram.
+///
+/// A decoder is cheap to clone, e.g. to snapshot it in the middle of a
+/// datagram and continue decoding with both copies independently.
+#[derive(Clone)]
+pub struct Decoder {
+    config: DecoderConfig,
+    state: DecoderState,
+    // The two most recently completed datagrams not yet taken, oldest first
+    completed: [Option<Datagram>; 2],
+}
+
+/// Configuration of a decoder, it is not changed by decoding
+#[derive(Copy, Clone)]
+struct DecoderConfig {
+    activity_level: ActivityLevel,
+    sync_on_turning_edge: SyncOnTurningEdge,
+    bit_order: BitOrder,
+    samples_per_half_bit: u8,
+    tolerance: u8,
+    // Accepted idle duration in half bits before a datagram starts
+    start_idle_window: Option<(u8, u8)>,
+    // Reject datagrams with a missing mid-bit edge
+    strict_mid_bit_edge: bool,
+    // Only emit datagrams with this prefix and length
+    filter: Option<(Datagram, u8)>,
+    // Number of samples ignored after start-up
+    startup_blanking: u8,
+    // Minimum length in half bits of a leading mark that is skipped
+    lead_mark_skip: Option<u8>,
+    // Emit a datagram every that many bits without waiting for idle
+    streaming_window: Option<u8>,
+    // Finalize a datagram as soon as it has that many bits
+    expected_length: Option<u8>,
+    // Samples without edge that end a datagram, default three half bits
+    idle_samples: Option<u8>,
+    // Longer datagrams are dropped
+    max_len: u8,
+    // Mapping of the mid-bit edge direction to the bit value
+    convention: Convention,
+    // Invert every bit of a standard encoding, e.g. behind an inverting receiver
+    inverted_bit_sense: bool,
+    // Report an idle line after that many half bits
+    idle_heartbeat: Option<u16>,
+    emit_timing: EmitTiming,
+    encoding: Encoding,
+    // Conversion of analog samples
+    threshold: Threshold,
+    // Consecutive samples a new level must last to be accepted as an edge
+    min_edge_samples: u8,
+}
+
+/// Runtime state of a decoder
+///
+/// The state is everything that changes while samples are processed.
+/// It is kept apart from the (immutable) configuration so that it can
+/// live elsewhere, e.g. in a `static` or in memory that is retained over
+/// a low-power cycle, while the configuration is a shared `const` [`Decoder`].
+///
+/// A fresh state is obtained via [`Decoder::initial_state`] and advanced
+/// via [`Decoder::next_with_state`].
+///
+/// The state takes at most 128 bytes: the datagram in progress, the one
+/// held back by [`EmitTiming::Deferred`], the [`DecoderStats`] and a few
+/// counters. The buffer of [`Decoder::take_completed`] is not part of it.
+#[derive(Copy, Clone, Debug)]
+pub struct DecoderState {
+    // Collected output data
+    datagram: Datagram,
+    // Internal processing control data
+    previous_sample: bool,
+    edge_distance: u8,
+    recording_distance: u8,
+    receiving_started: bool,
+    record_marker_reached: bool,
+    // An edge occurred between two record marker
+    intermediate_edge: bool,
+    idle_samples: u32,
+    // The idle heartbeat was returned since the last edge
+    heartbeat_fired: bool,
+    start_rejected: bool,
+    blanking_samples: u8,
+    lead_mark: LeadMark,
+    deferred: Option<Datagram>,
+    // Consecutive samples at a level different to the previous sample
+    pending_edge: u8,
+    stats: DecoderStats,
+}
+
+/// Link quality statistics of a [`Decoder`]
+///
+/// All counters saturate at their maximum.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecoderStats {
+    /// Datagrams rejected with a [`DecodeError`]
+    pub rejected_frames: u32,
+    /// Edges within a datagram that are neither in the tolerance window of
+    /// a bit boundary nor of a mid-bit edge
+    pub out_of_tolerance_edges: u32,
+    /// Datagrams completely received (and passing the filter)
+    pub completed_frames: u32,
+}
+
+/// Progress on skipping a leading mark (see `DecoderBuilder::with_lead_mark_skip`)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum LeadMark {
+    Waiting,
+    InBurst,
+    Armed,
+}
+
+/// Outcome of processing a single sample
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecodeEvent {
+    /// No datagram completed and no error detected
+    Idle,
+    /// A datagram is completely received
+    Datagram(Datagram),
+    /// The line is idle for the duration configured via
+    /// [`DecoderBuilder::with_idle_heartbeat`]
+    IdleTimeout,
+    /// The datagram in progress is rejected, except for
+    /// [`DecodeError::EdgeTooEarly`] which is only reported
+    Error(DecodeError),
+}
+
+/// What the decoder expects to happen next on the line
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExpectedEvent {
+    /// The first edge(s) of a datagram
+    FrameStart,
+    /// The edge in the middle of a bit
+    MidBit,
+    /// The boundary between two bits (with or without an edge)
+    Boundary,
+    /// No more edges, i.e. the end of the datagram
+    End,
+}
+
+/// Reasons why a datagram in progress is rejected
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// After a bit boundary the mandatory mid-bit edge did not occur in time
+    MissingMidBitEdge,
+    /// The input pin could not be read (see [`PinDecoder`])
+    PinReadFailed,
+    /// An edge follows the previous one sooner than a half bit (minus tolerance)
+    ///
+    /// The edge is still processed like any other, i.e. it does not change
+    /// what [`Decoder::next`] decodes.
+    EdgeTooEarly,
+    /// The line is stuck at the active level without edges
+    NoEdgeTimeout,
+    /// The datagram exceeds the maximum length (see
+    /// [`DecoderBuilder::with_max_len`]), it is discarded and the line is
+    /// ignored until it is idle again
+    CapacityExceeded,
+}
+
+/// Sample counts derived from the configured samples per half bit and tolerance
+///
+/// All values count samples since the last edge (or record marker).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimingParameters {
+    /// Earliest sample a full bit edge is expected at
+    pub lower: u8,
+    /// Latest sample a full bit edge is expected at
+    pub upper: u8,
+    /// Samples without edge that end a datagram
+    pub exit: u8,
+}
+
+/// Reasons why a decoder configuration can never decode a datagram
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// At least one sample per half bit period is required
+    ZeroSamplesPerHalfBit,
+    /// The derived barriers and exit limit do not fit into the sample counters
+    SamplesPerHalfBitTooLarge,
+    /// The configured exit limit does not fit into the sample counters
+    IdleSamplesTooLarge,
+    /// The tolerance windows of a half bit edge and a full bit edge overlap,
+    /// i.e. `2 * tolerance >= samples_per_half_bit`
+    ToleranceTooLarge,
+    /// The shortest half bit would be filtered as a glitch,
+    /// i.e. `min_edge_samples + tolerance > samples_per_half_bit`
+    MinEdgeSamplesTooLarge,
+    /// An edge within the upper barrier would already be treated as the end
+    /// of the datagram
+    BarrierExceedsExitLimit,
+    /// The minimum of the start idle window is larger than its maximum
+    InvalidStartIdleWindow,
+    /// The prefix of the filter is longer than the length of the filter
+    FilterPrefixTooLong,
+    /// The maximum length is zero or exceeds [`Datagram::MAX_BITS`]
+    InvalidMaxLen,
+    /// The length of the filter, the streaming window or the expected
+    /// length exceeds the maximum length
+    ExceedsMaxLen,
+    /// An expected length of zero bits would complete every datagram at once
+    ZeroExpectedLength,
+    /// A fixed expected length contradicts the windows of a streaming decoder
+    ExpectedLengthWithStreamingWindow,
+    /// The expected length differs from the length of the filter, i.e. no
+    /// completed datagram could ever pass the filter
+    ExpectedLengthMismatchesFilter,
+}
+
+const SAMPLES_PER_HALF_BIT_PERIOD: u8 = 3;
+const TOLERANCE: u8 = 1;
+
+//   ___---___------   e - first edge
+//   xxx012345678901   x - exit criteria no bits are send anymore
+//     f----tttt--xxx  t - tolerance range an edge is expected
+
+/// The exit limit must fit into a u8 including the one sample overshoot
+/// before the overflow prevention kicks in.
+const MAX_SAMPLES_PER_HALF_BIT_PERIOD: u8 = 84;
+
+/// The exit limit is compared after the sample counter is incremented.
+const MAX_IDLE_SAMPLES: u8 = u8::MAX - 1;
+
+impl Decoder {
+    /// Create an instance of a new manchester encoder
+    ///
+    /// # Arguments
+    ///
+    /// * `activity_level` - Low and High indicate what the activity level is
+    ///   the negation of the activity level is the inactivity
+    ///   level where no datagram is transmitted.
+    /// * `sync_on_turning_edge` - Indication if on the First or the Second
+    ///   edge bits are aligned.
+    /// * `bit_order` - Either BigEndian (MSP is received first) or
+    ///   LittleEndian (LSB is received first)
+    ///
+    /// In combination of activity_level and sync_on_turning_edge it is determined
+    /// what if the first bit is either zero or one
+    ///
+    /// | Activity level | Sync on turning edge | Resulting first bit datagram |
+    /// |----------------|----------------------|------------------------------|
+    /// | High           | First                | Zero                         |
+    /// | High           | Second               | One                          |
+    /// | Low            | First                | One                          |
+    /// | Low            | Second               | Zero                         |
+    ///
+    pub const fn new(
+        activity_level: ActivityLevel,
+        sync_on_turning_edge: SyncOnTurningEdge,
+        bit_order: BitOrder,
+    ) -> Self {
+        Decoder::from_config(DecoderConfig::new(
+            activity_level,
+            sync_on_turning_edge,
+            bit_order,
+        ))
+    }
+
+    const fn from_config(config: DecoderConfig) -> Self {
+        Decoder {
+            state: config.initial_state(),
+            config,
+            completed: [None, None],
+        }
+    }
+
+    /// Create a decoder for a different sampling rate
+    ///
+    /// Same as [`Decoder::new`] but a half bit is sampled `samples_per_half_bit`
+    /// times instead of 3 times, e.g. 9 samples of a 100 µs tick for the
+    /// 889 µs half bit of RC5. The barriers and the end of datagram limit
+    /// are derived from it (see [`Decoder::timing_parameters`]).
+    ///
+    /// The default tolerance of one sample requires at least three samples
+    /// per half bit. For two samples per half bit use the
+    /// [`DecoderBuilder`] with a tolerance of zero.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(decoder)` - if the timing is sane
+    /// * `Err(ConfigError)` - describing the first violated timing
+    ///   relationship (see [`Decoder::validate_timing`])
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, ConfigError, Decoder, SyncOnTurningEdge};
+    ///
+    /// let decoder = Decoder::with_sampling(
+    ///     ActivityLevel::Low,
+    ///     SyncOnTurningEdge::First,
+    ///     BitOrder::BigEndian,
+    ///     9,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(27, decoder.timing_parameters().exit);
+    ///
+    /// // half and full bit distances would overlap with the default tolerance
+    /// let decoder = Decoder::with_sampling(
+    ///     ActivityLevel::Low,
+    ///     SyncOnTurningEdge::First,
+    ///     BitOrder::BigEndian,
+    ///     2,
+    /// );
+    /// assert_eq!(Some(ConfigError::ToleranceTooLarge), decoder.err());
+    /// ```
+    pub const fn with_sampling(
+        activity_level: ActivityLevel,
+        sync_on_turning_edge: SyncOnTurningEdge,
+        bit_order: BitOrder,
+        samples_per_half_bit: u8,
+    ) -> Result<Self, ConfigError> {
+        let mut config = DecoderConfig::new(activity_level, sync_on_turning_edge, bit_order);
+        config.samples_per_half_bit = samples_per_half_bit;
+        match config.validate_timing() {
+            Ok(()) => Ok(Decoder::from_config(config)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Create a decoder for an active low infrared receiver
+    ///
+    /// Typical integrated demodulators (e.g. TSOP or VS1838 style) pull their
+    /// output low while a carrier is received, the line is high when idle.
+    /// The first bit is expected to be one, like the start bit of RC5.
+    ///
+    /// # Arguments
+    ///
+    /// * `bit_order` - Either BigEndian (MSP is received first) or
+    ///   LittleEndian (LSB is received first)
+    pub const fn for_active_low_receiver(bit_order: BitOrder) -> Self {
+        Decoder::with_mode(DecodeMode::FirstBitOneIdleHigh, bit_order)
+    }
+
+    /// Create a decoder for an active high infrared receiver
+    ///
+    /// Receivers with an inverting stage (or a bare photo diode with a
+    /// comparator) drive their output high while a carrier is received,
+    /// the line is low when idle.
+    /// The first bit is expected to be one, like the start bit of RC5.
+    ///
+    /// The sense of the bits is inverted compared to a decoder created with
+    /// [`ActivityLevel::High`], i.e. a transmission decodes to the same
+    /// datagram as with [`Decoder::for_active_low_receiver`] and the
+    /// inverted receiver output. The inversion is independent of the
+    /// [`Convention`], see [`DecoderBuilder::with_inverted_bit_sense`].
+    ///
+    /// # Arguments
+    ///
+    /// * `bit_order` - Either BigEndian (MSP is received first) or
+    ///   LittleEndian (LSB is received first)
+    pub const fn for_active_high_receiver(bit_order: BitOrder) -> Self {
+        let mut decoder = Decoder::new(ActivityLevel::High, SyncOnTurningEdge::First, bit_order);
+        decoder.config.inverted_bit_sense = true;
+        decoder
+    }
+
+    /// Create a decoder from a named mode
+    ///
+    /// Same as [`Decoder::new`] with the activity level and edge
+    /// synchronization given by `mode`, e.g. to avoid mixing them up.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{BitOrder, DecodeMode, Decoder};
+    ///
+    /// let decoder = Decoder::with_mode(DecodeMode::FirstBitOneIdleHigh, BitOrder::BigEndian);
+    /// assert!(decoder.inferred_first_bit());
+    /// ```
+    pub const fn with_mode(mode: DecodeMode, bit_order: BitOrder) -> Self {
+        let (activity_level, sync_on_turning_edge) = mode.expand();
+        Decoder::new(activity_level, sync_on_turning_edge, bit_order)
+    }
+
+    /// Create a decoder for a protocol with a known first bit
+    ///
+    /// The edge synchronization is chosen from the activity level and the
+    /// first bit by the table documented for [`Decoder::new`], e.g. RC5
+    /// always starts with a one.
+    ///
+    /// # Arguments
+    ///
+    /// * `activity_level` - The activity level of the receiver
+    /// * `first_bit` - The value of the first bit of every datagram
+    /// * `bit_order` - Either BigEndian (MSP is received first) or
+    ///   LittleEndian (LSB is received first)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, Datagram, Decoder};
+    ///
+    /// let mut decoder = Decoder::with_first_bit(ActivityLevel::Low, true, BitOrder::BigEndian);
+    /// let datagram = "--------......------...---...----------"
+    ///     .bytes()
+    ///     .find_map(|sample| decoder.next(sample == b'-'));
+    /// assert_eq!(Some(Datagram::new("1011")), datagram);
+    /// ```
+    pub const fn with_first_bit(
+        activity_level: ActivityLevel,
+        first_bit: bool,
+        bit_order: BitOrder,
+    ) -> Self {
+        let sync_on_turning_edge = match (&activity_level, first_bit) {
+            (ActivityLevel::High, false) | (ActivityLevel::Low, true) => SyncOnTurningEdge::First,
+            (ActivityLevel::High, true) | (ActivityLevel::Low, false) => SyncOnTurningEdge::Second,
+        };
+        Decoder::new(activity_level, sync_on_turning_edge, bit_order)
+    }
+
+    /// The value of the first bit of a datagram this decoder expects
+    ///
+    /// It follows from activity level and edge synchronization as
+    /// documented for [`Decoder::new`] and is inverted by the
+    /// [`Convention::Ieee8023`] convention and by an inverted bit sense
+    /// (see [`DecoderBuilder::with_inverted_bit_sense`]).
+    pub const fn inferred_first_bit(&self) -> bool {
+        let ge_thomas = matches!(
+            (
+                &self.config.activity_level,
+                &self.config.sync_on_turning_edge
+            ),
+            (ActivityLevel::High, SyncOnTurningEdge::Second)
+                | (ActivityLevel::Low, SyncOnTurningEdge::First)
+        );
+        ge_thomas
+            ^ matches!(self.config.convention, Convention::Ieee8023)
+            ^ self.config.inverted_bit_sense
+    }
+
+    /// Log the sample indices of edges while decoding
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, Decoder, SyncOnTurningEdge};
+    ///
+    /// let decoder = Decoder::new(ActivityLevel::Low, SyncOnTurningEdge::First, BitOrder::BigEndian);
+    /// let mut decoder = decoder.with_edge_log::<32>();
+    /// for sample in "--------......------...---...----------".bytes() {
+    ///     decoder.next(sample == b'-');
+    /// }
+    /// assert_eq!([8, 14, 20, 23, 26, 29], decoder.last_frame_edges());
+    /// ```
+    pub const fn with_edge_log<const N: usize>(self) -> EdgeLogDecoder<N> {
+        EdgeLogDecoder {
+            decoder: self,
+            sample_index: 0,
+            edges: [0; N],
+            edge_count: 0,
+            last_frame_edges: [0; N],
+            last_frame_edge_count: 0,
+        }
+    }
+
+    /// Decode with both interpretations of the first edge
+    ///
+    /// Meant for exploratory use if the right [`SyncOnTurningEdge`] is not
+    /// known, see [`AutoSyncDecoder`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, Datagram, DecoderBuilder, SyncOnTurningEdge};
+    ///
+    /// let decoder = DecoderBuilder::new(ActivityLevel::Low, SyncOnTurningEdge::Second, BitOrder::BigEndian)
+    ///     .with_filter(Datagram::default(), 4)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut decoder = decoder.with_auto_sync();
+    /// let mut decoded = None;
+    /// for sample in "--------......------...---...----------".bytes() {
+    ///     decoded = decoded.or(decoder.next(sample == b'-'));
+    /// }
+    /// assert_eq!(Some(Datagram::new("1011")), decoded);
+    /// ```
+    pub const fn with_auto_sync(self) -> AutoSyncDecoder {
+        let other = self.config.initial_state();
+        AutoSyncDecoder {
+            decoder: self,
+            other,
+        }
+    }
+
+    /// Provide the state a decoder of this configuration starts with
+    ///
+    /// Use it to set up an externally held state for [`Decoder::next_with_state`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, Decoder, DecoderState, SyncOnTurningEdge};
+    ///
+    /// const DECODER: Decoder = Decoder::new(
+    ///     ActivityLevel::High,
+    ///     SyncOnTurningEdge::First,
+    ///     BitOrder::BigEndian,
+    /// );
+    /// static mut STATE: DecoderState = DECODER.initial_state();
+    /// ```
+    pub const fn initial_state(&self) -> DecoderState {
+        self.config.initial_state()
+    }
+
+    /// Restore the state of a newly created decoder
+    ///
+    /// E.g. after sampling was paused or the input was switched, so that
+    /// a partially received datagram does not corrupt the next one. The
+    /// configuration is kept, completed datagrams not yet taken are dropped.
+    /// The statistics are cleared as well, use [`Decoder::reset_stats`] to
+    /// clear only them.
+    pub fn reset(&mut self) {
+        self.state = self.config.initial_state();
+        self.completed = [None, None];
+    }
+
+    /// Drop the datagram in progress, e.g. after the input could not be read
+    ///
+    /// Only the datagram in progress is dropped and counted as a rejected
+    /// frame, the line is ignored until it is idle again. Unlike
+    /// [`Decoder::reset`] the statistics and the completed datagrams not
+    /// yet taken are kept.
+    pub fn abort_frame(&mut self) {
+        if self.config.abort_frame(&mut self.state) {
+            self.state.stats.rejected_frames = self.state.stats.rejected_frames.saturating_add(1);
+        }
+    }
+
+    /// Link quality statistics since creation or the last reset
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, Decoder, SyncOnTurningEdge};
+    ///
+    /// let mut decoder = Decoder::new(ActivityLevel::Low, SyncOnTurningEdge::First, BitOrder::BigEndian);
+    /// for sample in "--------......------...---...----------".bytes() {
+    ///     decoder.next(sample == b'-');
+    /// }
+    /// assert_eq!(1, decoder.stats().completed_frames);
+    /// ```
+    pub fn stats(&self) -> DecoderStats {
+        self.state.stats
+    }
+
+    /// Reset all statistics to zero
+    pub fn reset_stats(&mut self) {
+        self.state.stats = DecoderStats::default();
+    }
+
+    /// Check that the configured timing is able to decode datagrams at all
+    ///
+    /// Sampling is done with integer samples per half bit. Not every
+    /// combination of samples per half bit and tolerance allows to tell
+    /// a half bit edge from a full bit edge or a full bit edge from the end
+    /// of a datagram. Such a decoder silently never returns a datagram.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - if the timing is sane
+    /// * `Err(ConfigError)` - describing the first violated timing relationship
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, Decoder, SyncOnTurningEdge};
+    ///
+    /// let decoder = Decoder::new(
+    ///     ActivityLevel::High,
+    ///     SyncOnTurningEdge::First,
+    ///     BitOrder::BigEndian,
+    /// );
+    /// assert_eq!(Ok(()), decoder.validate_timing());
+    /// ```
+    pub fn validate_timing(&self) -> Result<(), ConfigError> {
+        self.config.validate_timing()
+    }
+
+    /// Change the number of samples an edge may be early or late
+    ///
+    /// The barriers become `2 * samples_per_half_bit ± tolerance`, so a
+    /// larger tolerance copes with half bits that drift more, e.g. due to a
+    /// sloppy AGC of the receiver. The tolerance must stay below half of the
+    /// samples per half bit, so that a half bit and a full bit can still be
+    /// told apart.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - if the tolerance is applied
+    /// * `Err(ConfigError)` - if the tolerance does not fit to the sampling,
+    ///   the decoder is left unchanged
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, ConfigError, Decoder, SyncOnTurningEdge};
+    ///
+    /// let mut decoder = Decoder::with_sampling(
+    ///     ActivityLevel::Low,
+    ///     SyncOnTurningEdge::First,
+    ///     BitOrder::BigEndian,
+    ///     6,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(Ok(()), decoder.set_tolerance(2));
+    /// assert_eq!(Err(ConfigError::ToleranceTooLarge), decoder.set_tolerance(3));
+    /// assert_eq!(10, decoder.timing_parameters().lower);
+    /// ```
+    pub fn set_tolerance(&mut self, tolerance: u8) -> Result<(), ConfigError> {
+        let previous = self.config.tolerance;
+        self.config.tolerance = tolerance;
+        let result = self.config.validate_timing();
+        if result.is_err() {
+            self.config.tolerance = previous;
+        }
+        result
+    }
+
+    /// Provide the sample counts the decoder derives from its configuration
+    ///
+    /// Useful to document a configuration or to compare it against a scope
+    /// capture (see [`TimingParameters`]).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, Decoder, SyncOnTurningEdge};
+    ///
+    /// let decoder = Decoder::new(
+    ///     ActivityLevel::High,
+    ///     SyncOnTurningEdge::First,
+    ///     BitOrder::BigEndian,
+    /// );
+    /// assert_eq!(9, decoder.timing_parameters().exit);
+    /// ```
+    pub const fn timing_parameters(&self) -> TimingParameters {
+        TimingParameters {
+            lower: self.config.lower_barrier(),
+            upper: self.config.upper_barrier(),
+            exit: self.config.no_edge_exit_limit(),
+        }
+    }
+
+    /// Sample a manchester modulated signal periodically and extract datagrams
+    ///
+    /// To cover some jitter the sampling rate is three times the half bit frequency
+    /// i.e. an Infrared manchester decoded bit lasts  2x  889 us),
+    /// Thus sampling period should be 296 us.
+    ///
+    /// Note: three times the bit frequency is good enough to consider the Nyquist
+    /// criterion and some potential jitter in sending frequency.
+    ///
+    /// A datagram longer than [`Datagram::MAX_BITS`] (or the maximum length)
+    /// never panics: it is discarded and the remaining edges are ignored
+    /// until the line is idle again ([`DecodeError::CapacityExceeded`] via
+    /// [`Decoder::next_event`]).
+    ///
+    /// # Arguments
+    ///
+    ///  * `sample` - the level of the pin true equals high, false equals low
+    ///
+    /// # Returns
+    ///
+    ///  Option of an infrared datagram
+    ///
+    ///  * None - if no complete datagram is received
+    ///  * Some(datagram) - a completely received datagram
+    ///
+    pub fn next(&mut self, sample: bool) -> Option<Datagram> {
+        match self.next_event(sample) {
+            DecodeEvent::Datagram(datagram) => Some(datagram),
+            _ => None,
+        }
+    }
+
+    /// Sample a manchester modulated signal with an analog front-end
+    ///
+    /// Like [`Decoder::next`] but the sample is an analog level, e.g. an ADC
+    /// reading. It is converted with the hysteresis of the configured
+    /// [`Threshold`] (see [`DecoderBuilder::with_threshold`]), a level within
+    /// the hysteresis keeps the level of the previous sample.
+    ///
+    /// # Arguments
+    ///
+    ///  * `level` - the analog level of the pin
+    ///
+    /// # Returns
+    ///
+    ///  * None - if no complete datagram is received
+    ///  * Some(datagram) - a completely received datagram
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, Datagram, DecoderBuilder, SyncOnTurningEdge, Threshold};
+    ///
+    /// let mut decoder = DecoderBuilder::new(ActivityLevel::Low, SyncOnTurningEdge::First, BitOrder::BigEndian)
+    ///     .with_threshold(Threshold::new(1000, 2000))
+    ///     .build()
+    ///     .unwrap();
+    /// let mut decoded = None;
+    /// for sample in "--------......------...---...----------".bytes() {
+    ///     let level = if sample == b'-' { 3000 } else { 200 };
+    ///     decoded = decoded.or(decoder.next_analog(level));
+    /// }
+    /// assert_eq!(Some(Datagram::new("1011")), decoded);
+    /// ```
+    pub fn next_analog(&mut self, level: u16) -> Option<Datagram> {
+        let sample = self
+            .config
+            .threshold
+            .level(level, self.state.previous_sample);
+        self.next(sample)
+    }
+
+    /// Sample a manchester modulated signal and report what happened
+    ///
+    /// Like [`Decoder::next`] but rejected datagrams are reported instead of
+    /// being silently discarded.
+    ///
+    /// # Arguments
+    ///
+    ///  * `sample` - the level of the pin true equals high, false equals low
+    ///
+    /// # Returns
+    ///
+    ///  * `DecodeEvent::Idle` - if nothing noteworthy happened
+    ///  * `DecodeEvent::Datagram(datagram)` - a completely received datagram
+    ///  * `DecodeEvent::Error(error)` - the datagram in progress is rejected
+    ///
+    pub fn next_event(&mut self, sample: bool) -> DecodeEvent {
+        let event = self.config.next_ext(&mut self.state, sample);
+        if let DecodeEvent::Datagram(datagram) = event {
+            self.buffer_completed(datagram);
+        }
+        event
+    }
+
+    /// Keep a completed datagram for [`Decoder::take_completed`]
+    fn buffer_completed(&mut self, datagram: Datagram) {
+        if self.completed[1].is_some() {
+            self.completed[0] = self.completed[1].take();
+        }
+        match self.completed[0] {
+            None => self.completed[0] = Some(datagram),
+            Some(_) => self.completed[1] = Some(datagram),
+        }
+    }
+
+    /// Sample a manchester modulated signal and report what happened
+    ///
+    /// Same as [`Decoder::next_event`], which is the preferred name.
+    pub fn next_ext(&mut self, sample: bool) -> DecodeEvent {
+        self.next_event(sample)
+    }
+
+    /// Feed a batch of samples, e.g. to benchmark the decoding cost
+    ///
+    /// Behaves like calling [`Decoder::next`] for every sample. Nothing is
+    /// allocated, completed datagrams are only counted (and buffered for
+    /// [`Decoder::take_completed`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - The sample values in the order they were taken
+    ///
+    /// # Returns
+    ///
+    /// The number of completed datagrams
+    pub fn bench_feed(&mut self, samples: &[bool]) -> usize {
+        samples
+            .iter()
+            .filter(|sample| self.next(**sample).is_some())
+            .count()
+    }
+
+    /// Decode all samples of an iterator, e.g. to replay a captured signal
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - The sample values in the order they were taken
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding the completed datagrams as the samples are consumed
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, Datagram, Decoder, SyncOnTurningEdge};
+    ///
+    /// let mut decoder = Decoder::new(
+    ///     ActivityLevel::Low,
+    ///     SyncOnTurningEdge::First,
+    ///     BitOrder::BigEndian,
+    /// );
+    /// let signal = "--------......------...---...----------";
+    /// let mut datagrams = decoder.decode_all(signal.bytes().map(|sample| sample == b'-'));
+    /// assert_eq!(Some(Datagram::new("1011")), datagrams.next());
+    /// assert_eq!(None, datagrams.next());
+    /// ```
+    pub fn decode_all<I: Iterator<Item = bool>>(&mut self, samples: I) -> DecodeAll<'_, I> {
+        DecodeAll {
+            decoder: self,
+            samples,
+        }
+    }
+
+    /// Number of bits of the datagram in progress received so far
+    ///
+    /// It is zero while the line is idle and after a datagram completed.
+    pub fn progress(&self) -> u8 {
+        self.state.datagram.len()
+    }
+
+    /// Sample a manchester modulated signal and report event and progress
+    ///
+    /// Combines [`Decoder::next_event`] and [`Decoder::progress`], e.g. for
+    /// interactive tools.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample` - The sample value
+    ///
+    /// # Returns
+    ///
+    /// The event and the progress after processing the sample
+    pub fn step(&mut self, sample: bool) -> (DecodeEvent, u8) {
+        let event = self.next_event(sample);
+        (event, self.progress())
+    }
+
+    /// Take the oldest completed datagram that was not taken yet
+    ///
+    /// Besides being returned by [`Decoder::next`], completed datagrams are
+    /// kept in a double buffer. This allows an ISR to sample while the main
+    /// loop takes the datagrams whenever it gets to it without the risk
+    /// that a fast back-to-back datagram clobbers an unread one.
+    /// If both buffers are occupied, the oldest datagram is dropped, i.e.
+    /// the buffer never holds more than the two most recent datagrams.
+    /// The buffer belongs to the decoder, datagrams decoded via
+    /// [`Decoder::next_with_state`] are not buffered.
+    ///
+    /// # Returns
+    ///
+    ///  * None - if there is no completed datagram
+    ///  * Some(datagram) - the oldest completed datagram
+    ///
+    pub fn take_completed(&mut self) -> Option<Datagram> {
+        let completed = self.completed[0].take();
+        self.completed.swap(0, 1);
+        completed
+    }
+
+    /// Report what the decoder expects to happen next on the line
+    ///
+    /// This is read only introspection, e.g. for a visual debugger.
+    ///
+    /// * `FrameStart` - no datagram is being received
+    /// * `Boundary` - a mid-bit edge was just recorded, the bit boundary follows
+    /// * `MidBit` - the bit boundary is passed, the mid-bit edge follows
+    /// * `End` - the mid-bit edge is overdue, the datagram ends if no edge
+    ///   follows
+    pub fn expected_next(&self) -> ExpectedEvent {
+        let state = &self.state;
+        if !state.receiving_started {
+            ExpectedEvent::FrameStart
+        } else if state.recording_distance > self.config.upper_barrier() {
+            ExpectedEvent::End
+        } else if state.recording_distance > state.edge_distance
+            || state.recording_distance > self.config.samples_per_half_bit + self.config.tolerance
+        {
+            ExpectedEvent::MidBit
+        } else {
+            ExpectedEvent::Boundary
+        }
+    }
+
+    /// Sample a manchester modulated signal and hand completed datagrams to a queue
+    ///
+    /// Behaves like [`Decoder::next`] but instead of returning a completed
+    /// datagram it is enqueued into the producer side of a single producer
+    /// single consumer queue. This allows to sample in an ISR and to consume
+    /// the datagrams in a task or the main loop.
+    ///
+    /// # Arguments
+    ///
+    ///  * `sample` - the level of the pin true equals high, false equals low
+    ///  * `producer` - the producer side of the queue the datagrams are
+    ///    enqueued to
+    ///
+    /// # Returns
+    ///
+    ///  * `Ok(())` - if no datagram was completed or it was enqueued
+    ///  * `Err(datagram)` - the completed datagram that was dropped
+    ///    because the queue was full
+    ///
+    pub fn next_into_producer<const N: usize>(
+        &mut self,
+        sample: bool,
+        producer: &mut Producer<'_, Datagram, N>,
+    ) -> Result<(), Datagram> {
+        match self.next(sample) {
+            Some(datagram) => producer.enqueue(datagram),
+            None => Ok(()),
+        }
+    }
+
+    /// Sample a manchester modulated signal using an externally held state
+    ///
+    /// Behaves exactly like [`Decoder::next`] but the runtime state is
+    /// provided by the caller instead of being kept inside the decoder.
+    /// The decoder itself is not modified, thus it can be a shared `const`.
+    ///
+    /// # Arguments
+    ///
+    ///  * `state` - the runtime state, initially obtained via
+    ///    [`Decoder::initial_state`]
+    ///  * `sample` - the level of the pin true equals high, false equals low
+    ///
+    /// # Returns
+    ///
+    ///  * None - if no complete datagram is received
+    ///  * Some(datagram) - a completely received datagram
+    ///
+    pub fn next_with_state(&self, state: &mut DecoderState, sample: bool) -> Option<Datagram> {
+        match self.config.next_ext(state, sample) {
+            DecodeEvent::Datagram(datagram) => Some(datagram),
+            _ => None,
+        }
+    }
+}
+
+/// Iterator over the datagrams decoded from an iterator of samples
+///
+/// Created via [`Decoder::decode_all`].
+pub struct DecodeAll<'a, I> {
+    decoder: &'a mut Decoder,
+    samples: I,
+}
+
+impl<'a, I: Iterator<Item = bool>> Iterator for DecodeAll<'a, I> {
+    type Item = Datagram;
+
     fn next(&mut self) -> Option<Self::Item> {
-        if 0 < self.index {
-            self.index -= 1;
-            Some(1 == self.datagram[self.index])
+        let decoder = &mut *self.decoder;
+        self.samples.find_map(|sample| decoder.next(sample))
+    }
+}
+
+impl DecoderConfig {
+    const fn new(
+        activity_level: ActivityLevel,
+        sync_on_turning_edge: SyncOnTurningEdge,
+        bit_order: BitOrder,
+    ) -> Self {
+        DecoderConfig {
+            activity_level,
+            sync_on_turning_edge,
+            bit_order,
+            samples_per_half_bit: SAMPLES_PER_HALF_BIT_PERIOD,
+            tolerance: TOLERANCE,
+            start_idle_window: None,
+            strict_mid_bit_edge: false,
+            filter: None,
+            startup_blanking: 0,
+            lead_mark_skip: None,
+            streaming_window: None,
+            expected_length: None,
+            idle_samples: None,
+            max_len: Datagram::MAX_BITS,
+            convention: Convention::GeThomas,
+            inverted_bit_sense: false,
+            idle_heartbeat: None,
+            emit_timing: EmitTiming::Immediate,
+            encoding: Encoding::Standard,
+            threshold: Threshold::new(u16::MAX / 3, u16::MAX / 3 * 2),
+            min_edge_samples: 1,
+        }
+    }
+
+    const fn initial_state(&self) -> DecoderState {
+        let previous_sample = match self.activity_level {
+            ActivityLevel::High => false,
+            ActivityLevel::Low => true,
+        };
+        DecoderState {
+            datagram: Datagram {
+                buffer: 0,
+                length_in_bit: 0,
+            },
+            previous_sample,
+            edge_distance: self.no_edge_exit_limit(),
+            recording_distance: self.no_edge_exit_limit(),
+            receiving_started: false,
+            record_marker_reached: false,
+            intermediate_edge: false,
+            idle_samples: 0,
+            heartbeat_fired: false,
+            start_rejected: false,
+            blanking_samples: self.startup_blanking,
+            lead_mark: LeadMark::Waiting,
+            deferred: None,
+            pending_edge: 0,
+            stats: DecoderStats {
+                rejected_frames: 0,
+                out_of_tolerance_edges: 0,
+                completed_frames: 0,
+            },
+        }
+    }
+
+    /// Smallest recording distance where the mid-bit edge is accepted
+    const fn lower_barrier(&self) -> u8 {
+        self.samples_per_half_bit
+            .saturating_mul(2)
+            .saturating_sub(self.tolerance)
+    }
+
+    /// Largest recording distance where the mid-bit edge is accepted
+    const fn upper_barrier(&self) -> u8 {
+        self.samples_per_half_bit
+            .saturating_mul(2)
+            .saturating_add(self.tolerance)
+    }
+
+    /// Number of samples without an edge that terminate a datagram
+    const fn no_edge_exit_limit(&self) -> u8 {
+        match self.idle_samples {
+            Some(idle_samples) => idle_samples,
+            None => self.samples_per_half_bit.saturating_mul(3),
+        }
+    }
+
+    /// Check if an idle period of `idle_samples` may precede a datagram
+    fn accepts_start_idle(&self, idle_samples: u32) -> bool {
+        match self.start_idle_window {
+            None => true,
+            Some((min, max)) => {
+                let idle_half_bits = idle_samples / self.samples_per_half_bit as u32;
+                min as u32 <= idle_half_bits && idle_half_bits <= max as u32
+            }
+        }
+    }
+
+    /// Check if a completed datagram passes the filter
+    fn accepts(&self, datagram: &Datagram) -> bool {
+        match self.filter {
+            None => true,
+            Some((prefix, exact_len)) => {
+                datagram.len() == exact_len
+                    && prefix.len() <= exact_len
+                    && datagram
+                        .buffer
+                        .checked_shr((exact_len - prefix.len()) as u32)
+                        .unwrap_or(0)
+                        == prefix.buffer
+            }
+        }
+    }
+
+    const fn validate_timing(&self) -> Result<(), ConfigError> {
+        if self.samples_per_half_bit == 0 {
+            return Err(ConfigError::ZeroSamplesPerHalfBit);
+        }
+        if self.samples_per_half_bit > MAX_SAMPLES_PER_HALF_BIT_PERIOD {
+            return Err(ConfigError::SamplesPerHalfBitTooLarge);
+        }
+        if self.no_edge_exit_limit() > MAX_IDLE_SAMPLES {
+            return Err(ConfigError::IdleSamplesTooLarge);
+        }
+        if self.tolerance.saturating_mul(2) >= self.samples_per_half_bit {
+            return Err(ConfigError::ToleranceTooLarge);
+        }
+        if self.min_edge_samples.saturating_add(self.tolerance) > self.samples_per_half_bit {
+            return Err(ConfigError::MinEdgeSamplesTooLarge);
+        }
+        if self.lower_barrier() > self.upper_barrier()
+            || self.upper_barrier() >= self.no_edge_exit_limit()
+        {
+            return Err(ConfigError::BarrierExceedsExitLimit);
+        }
+        Ok(())
+    }
+
+    /// Hold back a level change until it lasted `min_edge_samples` samples
+    ///
+    /// Every accepted edge is delayed by the same number of samples, so
+    /// the distances between edges are kept.
+    fn filter_glitch(&self, state: &mut DecoderState, sample: bool) -> bool {
+        if sample == state.previous_sample {
+            state.pending_edge = 0;
+            return sample;
+        }
+        state.pending_edge = state.pending_edge.saturating_add(1);
+        if state.pending_edge >= self.min_edge_samples {
+            state.pending_edge = 0;
+            sample
+        } else {
+            state.previous_sample
+        }
+    }
+
+    /// Track the leading mark, returns true if the edge is part of it
+    fn skips_lead_mark_edge(&self, state: &mut DecoderState, sample: bool) -> bool {
+        let min_half_bits = match self.lead_mark_skip {
+            Some(min_half_bits) if state.lead_mark != LeadMark::Armed => min_half_bits,
+            _ => return false,
+        };
+        let active = sample == (self.activity_level == ActivityLevel::High);
+        if active {
+            state.lead_mark = LeadMark::InBurst;
+        } else if state.lead_mark == LeadMark::InBurst {
+            let min_samples = (min_half_bits as u32 * self.samples_per_half_bit as u32)
+                .saturating_sub(self.tolerance as u32);
+            state.lead_mark = if state.idle_samples >= min_samples {
+                // the settling gap starts, the next edge starts the data
+                LeadMark::Armed
+            } else {
+                LeadMark::Waiting
+            };
+        }
+        true
+    }
+
+    /// Process an edge of a datagram that is (about to be) received
+    ///
+    /// Returns the datagram if a streaming window got filled.
+    fn record_edge(
+        &self,
+        state: &mut DecoderState,
+        sample: bool,
+        sync: SyncOnTurningEdge,
+    ) -> Result<Option<Datagram>, DecodeError> {
+        let reference_edge = !state.receiving_started && self.encoding != Encoding::Standard;
+        if !state.receiving_started {
+            // cover the start of the telegram
+            match sync {
+                SyncOnTurningEdge::First => {
+                    // first edge is the record marker
+                    state.record_marker_reached = true;
+                    state.receiving_started = true;
+                }
+                SyncOnTurningEdge::Second => {
+                    // by protocol design it is guaranteed that there is a second edge
+                    // within half-bit time aka within samples_per_half_bit
+                    if state.edge_distance <= self.samples_per_half_bit + self.tolerance {
+                        // first edge at the record marker
+                        state.record_marker_reached = true;
+                        state.receiving_started = true;
+                    } else {
+                        // very first edge -> do nothing on purpose
+                    }
+                }
+            }
+        }
+        if state.recording_distance >= self.lower_barrier()
+            && state.recording_distance <= self.upper_barrier()
+        {
+            state.record_marker_reached = true;
+        }
+        if !state.record_marker_reached {
+            if state.receiving_started
+                && state.recording_distance.abs_diff(self.samples_per_half_bit) > self.tolerance
+            {
+                state.stats.out_of_tolerance_edges =
+                    state.stats.out_of_tolerance_edges.saturating_add(1);
+            }
+            state.intermediate_edge = true;
+            // the edge sample counts towards the distance like any other
+            state.recording_distance += 1;
+        } else if reference_edge && state.receiving_started {
+            // the first clock edge of a differential encoding carries no bit
+            state.recording_distance = 1;
+            state.record_marker_reached = false;
+            state.intermediate_edge = false;
+        } else {
+            // In the middle of a bit transmission the value is derived from the new sample
+            // the sample is NOT mixed with activity_level
+            let bit = match self.encoding {
+                Encoding::Standard => {
+                    (sample == (self.convention == Convention::Ieee8023)) ^ self.inverted_bit_sense
+                }
+                Encoding::DifferentialMark => state.intermediate_edge,
+                Encoding::DifferentialSpace => !state.intermediate_edge,
+            };
+            state.intermediate_edge = false;
+            if state.datagram.len() >= self.max_len
+                || state.datagram.add_bit(bit, self.bit_order).is_err()
+            {
+                // too long to be recorded: drop it rather than panic in the ISR
+                state.datagram = Datagram::default();
+                state.receiving_started = false;
+                state.start_rejected = true;
+                return Err(DecodeError::CapacityExceeded);
+            }
+            // reset internal data for the next record_marker
+            state.recording_distance = 1;
+            state.record_marker_reached = false;
+            if self.streaming_window == Some(state.datagram.len()) {
+                // keep receiving, the next bit starts the next window
+                return Ok(Some(core::mem::take(&mut state.datagram)));
+            }
+            if self.expected_length == Some(state.datagram.len()) {
+                // the frame is complete, ignore the line until it is idle again
+                state.receiving_started = false;
+                state.start_rejected = true;
+                state.lead_mark = LeadMark::Waiting;
+                return Ok(Some(core::mem::take(&mut state.datagram)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Drop the datagram in progress and ignore the line until it is idle
+    ///
+    /// Returns true if a datagram was in progress.
+    fn abort_frame(&self, state: &mut DecoderState) -> bool {
+        let in_progress = state.receiving_started || !state.datagram.is_empty();
+        state.datagram = Datagram::default();
+        state.receiving_started = false;
+        state.record_marker_reached = false;
+        state.intermediate_edge = false;
+        state.start_rejected = true;
+        state.lead_mark = LeadMark::Waiting;
+        in_progress
+    }
+
+    /// Report a completely received datagram unless it is filtered
+    fn complete(&self, datagram: Datagram) -> DecodeEvent {
+        if self.accepts(&datagram) {
+            DecodeEvent::Datagram(datagram)
+        } else {
+            DecodeEvent::Idle
+        }
+    }
+
+    fn next_ext(&self, state: &mut DecoderState, sample: bool) -> DecodeEvent {
+        self.next_ext_synced(state, sample, self.sync_on_turning_edge)
+    }
+
+    /// Like `next_ext` but with an explicit interpretation of the first edge
+    fn next_ext_synced(
+        &self,
+        state: &mut DecoderState,
+        sample: bool,
+        sync: SyncOnTurningEdge,
+    ) -> DecodeEvent {
+        // To understand the algorithm record marker are introduced.
+        //
+        // Record marker are the sample taken directly after the edge
+        // in the middle of the transmission of a bit
+        // Record marker are aligned to SyncOnTurningEdge.
+        //
+        // As of the manchester protocol definition, there must be always
+        // an edge in the middle of transmission of the bit.
+        //
+        // Example: Start with "1" (high inactivity)
+        //
+        //        |bit_1|bit_0|bit_0     - The bits
+        // -------...------...---...     - The signal
+        //           ^     ^     ^       - The record marker
+        //
+        // Example: Start with "0" (high inactivity)
+        //
+        //        |bit_0|bit_1|bit_1     - The bits
+        // ----------......---...---     - The signal
+        //           ^     ^     ^       - The record marker
+        //
+        // At each record marker the bit value is determined and recorded
+        let mut event = DecodeEvent::Idle;
+
+        if state.blanking_samples > 0 {
+            // the input is not settled yet
+            state.blanking_samples -= 1;
+            state.previous_sample = sample;
+            return event;
+        }
+        let sample = self.filter_glitch(state, sample);
+        if sample != state.previous_sample {
+            if state.edge_distance >= self.no_edge_exit_limit() {
+                // first edge after an idle period
+                state.start_rejected = !self.accepts_start_idle(state.idle_samples);
+            }
+            if state.start_rejected {
+                // ignore the datagram until the line is idle again
+            } else if self.skips_lead_mark_edge(state, sample) {
+                // the edge belongs to the leading mark
+            } else if self.strict_mid_bit_edge
+                && state.receiving_started
+                && state.recording_distance > state.edge_distance
+                && state.edge_distance > self.samples_per_half_bit + self.tolerance
+            {
+                // the previous edge was at a bit boundary, but no mid-bit edge followed
+                event = DecodeEvent::Error(DecodeError::MissingMidBitEdge);
+                state.datagram = Datagram::default();
+                state.receiving_started = false;
+                state.start_rejected = true;
+            } else {
+                // the edges are closer than any half bit, it is only reported
+                let too_early = state.receiving_started
+                    && state.edge_distance + self.tolerance < self.samples_per_half_bit;
+                match self.record_edge(state, sample, sync) {
+                    Ok(Some(window)) => event = self.complete(window),
+                    Ok(None) if too_early => event = DecodeEvent::Error(DecodeError::EdgeTooEarly),
+                    Ok(None) => (),
+                    Err(error) => event = DecodeEvent::Error(error),
+                }
+            }
+            state.previous_sample = sample;
+            state.edge_distance = 1;
+            state.idle_samples = 1;
+            state.heartbeat_fired = false;
         } else {
-            None
+            state.edge_distance += 1;
+            state.recording_distance += 1;
+            state.idle_samples = state.idle_samples.saturating_add(1);
+        }
+
+        if state.edge_distance > self.no_edge_exit_limit() {
+            // end of datagram condition no edge anymore
+            // (a differential encoding may end at either level)
+            let idle = self.encoding != Encoding::Standard
+                || sample ^ (self.activity_level == ActivityLevel::High);
+            if idle {
+                // an empty datagram ends reception only if a streaming window
+                // took its bits or it started with a differential reference edge
+                if !state.datagram.is_empty()
+                    || self.streaming_window.is_some()
+                    || self.encoding != Encoding::Standard
+                {
+                    state.receiving_started = false;
+                }
+                if !state.datagram.is_empty() {
+                    state.lead_mark = LeadMark::Waiting;
+                    event = self.complete(state.datagram);
+                }
+            } else if !state.datagram.is_empty() {
+                event = DecodeEvent::Error(DecodeError::NoEdgeTimeout);
+            }
+            state.datagram = Datagram::default();
+            state.edge_distance -= 1; // prevent number overflow
+        }
+        if state.recording_distance > self.no_edge_exit_limit() {
+            state.recording_distance -= 1; // prevent number overflow
+        }
+        match event {
+            DecodeEvent::Datagram(_) => {
+                state.stats.completed_frames = state.stats.completed_frames.saturating_add(1)
+            }
+            // an early edge does not reject the datagram
+            DecodeEvent::Error(DecodeError::EdgeTooEarly) => (),
+            DecodeEvent::Error(_) => {
+                state.stats.rejected_frames = state.stats.rejected_frames.saturating_add(1)
+            }
+            _ => (),
+        }
+        if let Some(after_half_bits) = self.idle_heartbeat {
+            // a sample taken by another event postpones the heartbeat
+            if after_half_bits > 0
+                && !state.heartbeat_fired
+                && event == DecodeEvent::Idle
+                && state.idle_samples >= after_half_bits as u32 * self.samples_per_half_bit as u32
+                && sample ^ (self.activity_level == ActivityLevel::High)
+            {
+                state.heartbeat_fired = true;
+                event = DecodeEvent::IdleTimeout;
+            }
+        }
+        if self.emit_timing == EmitTiming::Deferred {
+            match event {
+                DecodeEvent::Datagram(datagram) => {
+                    event = match state.deferred.replace(datagram) {
+                        Some(deferred) => DecodeEvent::Datagram(deferred),
+                        None => DecodeEvent::Idle,
+                    };
+                }
+                DecodeEvent::Idle => {
+                    if let Some(datagram) = state.deferred.take() {
+                        event = DecodeEvent::Datagram(datagram);
+                    }
+                }
+                // the datagram waits behind any other event for an idle call
+                _ => (),
+            }
         }
+        event
     }
 }
 
-#[derive(Debug)]
-pub struct DatagramLittleEndianIterator {
-    datagram: Datagram,
-    index: u8,
+/// Build a decoder with non default options
+///
+/// Options are collected first, [`DecoderBuilder::build`] then checks that
+/// the options are consistent with each other and rejects contradictory
+/// combinations instead of letting one option silently win at runtime.
+///
+/// | Option                      | Conflicts with              | Conflict if                 | Error                                              |
+/// |-----------------------------|-----------------------------|-----------------------------|----------------------------------------------------|
+/// | `with_samples_per_half_bit` | -                           | zero samples                | [`ConfigError::ZeroSamplesPerHalfBit`]             |
+/// | `with_samples_per_half_bit` | -                           | more than 84 samples        | [`ConfigError::SamplesPerHalfBitTooLarge`]         |
+/// | `with_tolerance`            | `with_samples_per_half_bit` | `2 * tolerance >= samples`  | [`ConfigError::ToleranceTooLarge`]                 |
+/// | `with_min_edge_samples`     | `with_tolerance`            | `min + tol > samples`       | [`ConfigError::MinEdgeSamplesTooLarge`]            |
+/// | `with_idle_samples`         | -                           | more than 254 samples       | [`ConfigError::IdleSamplesTooLarge`]               |
+/// | `with_idle_samples`         | `with_tolerance`            | `idle <= 2 * samples + tol` | [`ConfigError::BarrierExceedsExitLimit`]           |
+/// | `with_start_idle_window`    | -                           | `min > max`                 | [`ConfigError::InvalidStartIdleWindow`]            |
+/// | `with_filter`               | -                           | `prefix.len() > exact_len`  | [`ConfigError::FilterPrefixTooLong`]               |
+/// | `with_max_len`              | -                           | zero or more than 128 bits  | [`ConfigError::InvalidMaxLen`]                     |
+/// | `with_filter`               | `with_max_len`              | `exact_len > max_len`       | [`ConfigError::ExceedsMaxLen`]                     |
+/// | `with_streaming_window`     | `with_max_len`              | `bits > max_len`            | [`ConfigError::ExceedsMaxLen`]                     |
+/// | `with_expected_length`      | `with_max_len`              | `bits > max_len`            | [`ConfigError::ExceedsMaxLen`]                     |
+/// | `with_expected_length`      | -                           | zero bits                   | [`ConfigError::ZeroExpectedLength`]                |
+/// | `with_expected_length`      | `with_streaming_window`     | both are set                | [`ConfigError::ExpectedLengthWithStreamingWindow`] |
+/// | `with_expected_length`      | `with_filter`               | `bits != exact_len`         | [`ConfigError::ExpectedLengthMismatchesFilter`]    |
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{ActivityLevel, BitOrder, DecoderBuilder, SyncOnTurningEdge};
+///
+/// let decoder = DecoderBuilder::new(
+///     ActivityLevel::High,
+///     SyncOnTurningEdge::First,
+///     BitOrder::BigEndian,
+/// )
+/// .with_samples_per_half_bit(9)
+/// .with_tolerance(2)
+/// .build();
+/// assert!(decoder.is_ok());
+/// ```
+pub struct DecoderBuilder {
+    config: DecoderConfig,
 }
 
-impl Iterator for DatagramLittleEndianIterator {
-    type Item = bool;
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.datagram.len() > self.index {
-            self.index += 1;
-            Some(1 == self.datagram[self.index - 1])
-        } else {
-            None
+impl DecoderBuilder {
+    /// Start building a decoder
+    ///
+    /// The arguments are the same as for [`Decoder::new`]; all other options
+    /// start with the values [`Decoder::new`] uses.
+    pub const fn new(
+        activity_level: ActivityLevel,
+        sync_on_turning_edge: SyncOnTurningEdge,
+        bit_order: BitOrder,
+    ) -> Self {
+        DecoderBuilder {
+            config: DecoderConfig::new(activity_level, sync_on_turning_edge, bit_order),
+        }
+    }
+
+    /// Drop datagrams longer than `bits` (default [`Datagram::MAX_BITS`])
+    ///
+    /// Longer datagrams are dropped like any datagram exceeding the
+    /// capacity ([`DecodeError::CapacityExceeded`]), e.g. to reject
+    /// overlong noise early. The completed datagrams are still
+    /// [`Datagram`]s, i.e. their storage and arithmetic stay 128 bit wide.
+    /// [`DecoderBuilder::build`] checks that the length of the filter, the
+    /// streaming window and the expected length fit.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, DecoderBuilder, SyncOnTurningEdge};
+    ///
+    /// let decoder = DecoderBuilder::new(
+    ///     ActivityLevel::High,
+    ///     SyncOnTurningEdge::First,
+    ///     BitOrder::BigEndian,
+    /// )
+    /// .with_max_len(32)
+    /// .build();
+    /// assert!(decoder.is_ok());
+    /// ```
+    pub const fn with_max_len(mut self, bits: u8) -> Self {
+        self.config.max_len = bits;
+        self
+    }
+
+    /// Number of samples taken per half bit period (default 3)
+    ///
+    /// Two samples per half bit are the minimum, they require a tolerance
+    /// of zero (see [`DecoderBuilder::with_tolerance`]), i.e. no jitter is
+    /// tolerated.
+    pub const fn with_samples_per_half_bit(mut self, samples_per_half_bit: u8) -> Self {
+        self.config.samples_per_half_bit = samples_per_half_bit;
+        self
+    }
+
+    /// Number of consecutive samples a new level must last (default 1)
+    ///
+    /// Shorter pulses, e.g. single sample spikes on a noisy line, are
+    /// ignored instead of being taken as an edge. Each accepted edge is
+    /// delayed by `min_edge_samples - 1` samples. The shortest half bit,
+    /// `samples_per_half_bit - tolerance`, must still pass the filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_edge_samples` - the number of samples (zero and one disable the filter)
+    pub const fn with_min_edge_samples(mut self, min_edge_samples: u8) -> Self {
+        self.config.min_edge_samples = min_edge_samples;
+        self
+    }
+
+    /// Number of samples without edge that end a datagram
+    ///
+    /// The line must stay at the inactive level that long before a datagram
+    /// is considered complete. The default is three half bits, i.e.
+    /// `3 * samples_per_half_bit`. A longer limit tolerates protocols with
+    /// guard intervals, it has to exceed the upper barrier of a full bit
+    /// edge (see [`Decoder::timing_parameters`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `idle_samples` - the number of samples (at most 254)
+    pub const fn with_idle_samples(mut self, idle_samples: u8) -> Self {
+        self.config.idle_samples = Some(idle_samples);
+        self
+    }
+
+    /// Number of samples an edge may be early or late (default 1)
+    ///
+    /// The tolerance must stay below half of the samples per half bit,
+    /// otherwise [`DecoderBuilder::build`] fails with
+    /// [`ConfigError::ToleranceTooLarge`].
+    pub const fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.config.tolerance = tolerance;
+        self
+    }
+
+    /// Only accept a datagram if the preceding idle period is within a window
+    ///
+    /// The idle period is the time the line stayed at the same level before the
+    /// first edge of a datagram. It is measured in (complete) half bits.
+    /// A datagram whose idle period is too short or too long is ignored
+    /// entirely. For the very first datagram the idle period is measured
+    /// from the creation of the decoder.
+    ///
+    /// Note: The end of a datagram requires an idle period of 1.5 bits, thus
+    /// an idle period shorter than three half bits never occurs.
+    ///
+    /// # Arguments
+    ///
+    /// * `min` - minimum idle period in half bits (included)
+    /// * `max` - maximum idle period in half bits (included)
+    pub const fn with_start_idle_window(mut self, min: u8, max: u8) -> Self {
+        self.config.start_idle_window = Some((min, max));
+        self
+    }
+
+    /// Reject a datagram if a mandatory mid-bit edge is missing
+    ///
+    /// By default a missing mid-bit edge is not detected as such; the
+    /// decoder keeps waiting for the next edge that fits. In strict mode
+    /// a bit boundary edge that is not followed by the mid-bit edge within
+    /// half a bit (plus tolerance) rejects the datagram with
+    /// [`DecodeError::MissingMidBitEdge`] (see [`Decoder::next_event`]).
+    pub const fn with_strict_mid_bit_edge(mut self, strict: bool) -> Self {
+        self.config.strict_mid_bit_edge = strict;
+        self
+    }
+
+    /// Only emit datagrams of a given length that start with a given prefix
+    ///
+    /// All other datagrams are silently dropped, e.g. to listen to a single
+    /// device on a shared medium. The leading bits are the most significant ones
+    /// (i.e. the first ones transmitted in big endian bit order), the same
+    /// way `Datagram::new` records them.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - the bits a datagram must start with
+    /// * `exact_len` - the length a datagram must have
+    pub const fn with_filter(mut self, prefix: Datagram, exact_len: u8) -> Self {
+        self.config.filter = Some((prefix, exact_len));
+        self
+    }
+
+    /// Ignore the first samples after start-up
+    ///
+    /// At power-up or after enabling the receiver the input may chatter for
+    /// a few samples before settling. The first `n` samples only set the
+    /// line level, edge detection is armed afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - the number of samples ignored
+    pub const fn with_startup_blanking(mut self, n: u8) -> Self {
+        self.config.startup_blanking = n;
+        self
+    }
+
+    /// Skip a long leading mark before the data
+    ///
+    /// Some protocols (e.g. NEC) start with a long burst followed by a
+    /// settling gap before the data. With this option the decoder waits for
+    /// an active burst of at least `min_half_bits` half bits (minus tolerance
+    /// samples). The burst is not decoded, the first edge after the gap
+    /// starts the datagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_half_bits` - the minimum length of the leading mark
+    pub const fn with_lead_mark_skip(mut self, min_half_bits: u8) -> Self {
+        self.config.lead_mark_skip = Some(min_half_bits);
+        self
+    }
+
+    /// Emit a datagram every `bits` decoded bits in continuous streams
+    ///
+    /// For continuous links without framing a datagram is emitted as soon
+    /// as `bits` bits are decoded and decoding continues with the next
+    /// window. The remaining bits of an incomplete window are emitted as
+    /// a shorter datagram once the line gets idle.
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - the number of bits of a window (1..=128)
+    pub const fn with_streaming_window(mut self, bits: u8) -> Self {
+        self.config.streaming_window = Some(bits);
+        self
+    }
+
+    /// Finalize a datagram as soon as it reaches a known fixed length
+    ///
+    /// For fixed length protocols (e.g. 14 bit RC5) the datagram is emitted
+    /// at the record marker of its last bit instead of after the trailing
+    /// idle period. Edges until the line gets idle again are ignored.
+    ///
+    /// It cannot be combined with a streaming window, and a filter has to
+    /// require the same length (see the conflicts of [`DecoderBuilder`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - the number of bits of a datagram (1..=128)
+    pub const fn with_expected_length(mut self, bits: u8) -> Self {
+        self.config.expected_length = Some(bits);
+        self
+    }
+
+    /// Report a line that is idle for a while
+    ///
+    /// [`Decoder::next_event`] returns [`DecodeEvent::IdleTimeout`] once the
+    /// line is continuously at the inactive level for `after_half_bits` half
+    /// bits, e.g. to grey out a UI if there is no remote activity. The event
+    /// is returned once per idle period, the next one requires an edge first.
+    /// If another event is returned for that sample (e.g. the datagram just
+    /// completed by the idle line), the heartbeat follows with the next one.
+    ///
+    /// # Arguments
+    ///
+    /// * `after_half_bits` - the idle duration in half bits (zero disables)
+    pub const fn with_idle_heartbeat(mut self, after_half_bits: u16) -> Self {
+        self.config.idle_heartbeat = Some(after_half_bits);
+        self
+    }
+
+    /// Mapping of the mid-bit edge direction to the bit value (default [`Convention::GeThomas`])
+    ///
+    /// Use the same convention as the encoder, see [`Encoder::with_convention`].
+    pub const fn with_convention(mut self, convention: Convention) -> Self {
+        self.config.convention = convention;
+        self
+    }
+
+    /// Invert every decoded bit (default `false`)
+    ///
+    /// Compensates an inverting receiver stage like
+    /// [`Decoder::for_active_high_receiver`] does. It is applied on top of
+    /// the [`Convention`], i.e. both can be chosen independently.
+    /// A differential encoding does not depend on the polarity and is not
+    /// affected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{ActivityLevel, BitOrder, Datagram, DecoderBuilder, SyncOnTurningEdge};
+    ///
+    /// let mut decoder = DecoderBuilder::new(
+    ///     ActivityLevel::High,
+    ///     SyncOnTurningEdge::First,
+    ///     BitOrder::BigEndian,
+    /// )
+    /// .with_inverted_bit_sense(true)
+    /// .build()
+    /// .unwrap();
+    /// let datagram = "........------......---...---..........."
+    ///     .bytes()
+    ///     .find_map(|sample| decoder.next(sample == b'-'));
+    /// assert_eq!(Some(Datagram::new("1011")), datagram);
+    /// ```
+    pub const fn with_inverted_bit_sense(mut self, inverted: bool) -> Self {
+        self.config.inverted_bit_sense = inverted;
+        self
+    }
+
+    /// Decode a differential encoding (default [`Encoding::Standard`])
+    pub const fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.config.encoding = encoding;
+        self
+    }
+
+    /// Choose the call on which a completed datagram is returned
+    ///
+    /// By default the datagram is returned on the call that detects the end
+    /// of the datagram. With [`EmitTiming::Deferred`] it is returned on the
+    /// following call instead, or on the first call after it that reports
+    /// no other event, i.e. errors and idle timeouts are never replaced.
+    pub const fn with_emit_timing(mut self, timing: EmitTiming) -> Self {
+        self.config.emit_timing = timing;
+        self
+    }
+
+    /// Levels to convert analog samples (default the `u16` range split in thirds)
+    ///
+    /// Only used by [`Decoder::next_analog`].
+    pub const fn with_threshold(mut self, threshold: Threshold) -> Self {
+        self.config.threshold = threshold;
+        self
+    }
+
+    /// Check the options for consistency and create the decoder
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(decoder)` - if the options are consistent
+    /// * `Err(ConfigError)` - naming the first detected conflict
+    pub fn build(self) -> Result<Decoder, ConfigError> {
+        self.config.validate_timing()?;
+        if self.config.max_len == 0 || self.config.max_len > Datagram::MAX_BITS {
+            return Err(ConfigError::InvalidMaxLen);
+        }
+        if let Some((min, max)) = self.config.start_idle_window {
+            if min > max {
+                return Err(ConfigError::InvalidStartIdleWindow);
+            }
+        }
+        if let Some((prefix, exact_len)) = self.config.filter {
+            if prefix.len() > exact_len {
+                return Err(ConfigError::FilterPrefixTooLong);
+            }
+            if exact_len > self.config.max_len {
+                return Err(ConfigError::ExceedsMaxLen);
+            }
+        }
+        if let Some(window) = self.config.streaming_window {
+            if window > self.config.max_len {
+                return Err(ConfigError::ExceedsMaxLen);
+            }
+        }
+        if let Some(bits) = self.config.expected_length {
+            if bits == 0 {
+                return Err(ConfigError::ZeroExpectedLength);
+            }
+            if bits > self.config.max_len {
+                return Err(ConfigError::ExceedsMaxLen);
+            }
+            if self.config.streaming_window.is_some() {
+                return Err(ConfigError::ExpectedLengthWithStreamingWindow);
+            }
+            if let Some((_, exact_len)) = self.config.filter {
+                if bits != exact_len {
+                    return Err(ConfigError::ExpectedLengthMismatchesFilter);
+                }
+            }
         }
+        Ok(Decoder::from_config(self.config))
     }
 }
 
-/// Encodes a datagram to Manchester code
+/// A decoder that additionally captures the samples of the last datagram
 ///
-/// The encoder turns into an iterator.
-/// the encoding happens by calling the iterator.
+/// The samples are recorded into a buffer provided by the caller as the
+/// decoder sees them, i.e. after the glitch filter (see
+/// [`DecoderBuilder::with_min_edge_samples`]) and without the samples
+/// ignored by [`DecoderBuilder::with_startup_blanking`]. Recording starts
+/// at the first edge of a datagram. When the datagram completes, the
+/// samples up to and including the sample that completed it can be
+/// inspected via [`CapturingDecoder::last_frame_samples`], e.g. for offline
+/// analysis during field debugging. They are kept until the first edge of
+/// the next datagram.
+///
+/// If a datagram spans more samples than the buffer holds only the latest
+/// samples are preserved.
 ///
 /// # Example
 ///
 /// ```rust
-/// use manchester_code::{
-///     Datagram,
-///     DatagramBigEndianIterator,
-///     Encoder};
+/// use manchester_code::{ActivityLevel, BitOrder, CapturingDecoder, Decoder, SyncOnTurningEdge};
 ///
-/// let mut encoder = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("01"));
-/// assert_eq!(Some(true), encoder.next());
-/// assert_eq!(Some(false), encoder.next());
-/// assert_eq!(Some(false), encoder.next());
-/// assert_eq!(Some(true), encoder.next());
-/// assert_eq!(None, encoder.next());
+/// let mut buffer = [false; 64];
+/// let decoder = Decoder::new(ActivityLevel::Low, SyncOnTurningEdge::First, BitOrder::BigEndian);
+/// let mut decoder = CapturingDecoder::new(decoder, &mut buffer);
+/// for sample in "--------......------...---...----------".bytes() {
+///     decoder.next(sample == b'-');
+/// }
+/// assert_eq!(31, decoder.last_frame_samples().len());
+/// ```
+pub struct CapturingDecoder<'a> {
+    decoder: Decoder,
+    buffer: &'a mut [bool],
+    // Position the next sample is recorded at
+    head: usize,
+    // Samples recorded since the first edge of the datagram in progress
+    frame_samples: usize,
+    // The datagram in progress is being recorded
+    recording: bool,
+    last_frame_len: usize,
+}
+
+impl<'a> CapturingDecoder<'a> {
+    /// Create a new capturing decoder
+    ///
+    /// # Arguments
+    ///
+    /// * `decoder` - The decoder that does the actual decoding
+    /// * `buffer` - The storage of the captured samples, its length limits
+    ///   the number of samples preserved per datagram
+    pub fn new(decoder: Decoder, buffer: &'a mut [bool]) -> Self {
+        CapturingDecoder {
+            decoder,
+            buffer,
+            head: 0,
+            frame_samples: 0,
+            recording: false,
+            last_frame_len: 0,
+        }
+    }
+
+    /// Sample a manchester modulated signal and capture the sample
+    ///
+    /// Apart from capturing it behaves like [`Decoder::next`].
+    pub fn next(&mut self, sample: bool) -> Option<Datagram> {
+        let state = &self.decoder.state;
+        let blanking = state.blanking_samples > 0;
+        let previous_sample = state.previous_sample;
+        let idle = state.edge_distance >= self.decoder.config.no_edge_exit_limit();
+
+        let datagram = self.decoder.next(sample);
+        if blanking || self.buffer.is_empty() {
+            return datagram;
+        }
+        // the level the decoder accepted, i.e. after the glitch filter
+        let sample = self.decoder.state.previous_sample;
+        if sample != previous_sample && idle {
+            // first edge after an idle period
+            self.head = 0;
+            self.frame_samples = 0;
+            self.recording = true;
+            self.last_frame_len = 0;
+        }
+        if self.recording {
+            self.buffer[self.head] = sample;
+            self.head = (self.head + 1) % self.buffer.len();
+            if self.frame_samples < self.buffer.len() {
+                self.frame_samples += 1;
+            }
+        }
+        if datagram.is_some() {
+            // the oldest sample is at the head once the buffer wrapped around
+            if self.frame_samples == self.buffer.len() {
+                self.buffer.rotate_left(self.head);
+            }
+            self.recording = false;
+            self.last_frame_len = self.frame_samples;
+        }
+        datagram
+    }
+
+    /// The samples that made up the most recently completed datagram
+    ///
+    /// Feeding these samples into a fresh decoder of the same configuration,
+    /// but without glitch filter and startup blanking, reproduces the
+    /// datagram on the last sample.
+    pub fn last_frame_samples(&self) -> &[bool] {
+        &self.buffer[..self.last_frame_len]
+    }
+}
+
+/// A decoder trying both interpretations of the first edge
+///
+/// Besides the configured interpretation of the first edge the other one
+/// is decoded in parallel. The first datagram that validates is returned,
+/// i.e. that passes the filter configured via
+/// [`DecoderBuilder::with_filter`]. If both interpretations validate at the
+/// same sample, the configured one wins. The other interpretation then
+/// drops its datagram in progress and waits for the line to get idle, so
+/// a transmission yields at most one datagram.
+///
+/// Created via [`Decoder::with_auto_sync`].
+pub struct AutoSyncDecoder {
+    decoder: Decoder,
+    // Decoding with the other interpretation of the first edge
+    other: DecoderState,
+}
+
+impl AutoSyncDecoder {
+    /// Sample a manchester modulated signal trying both interpretations
+    ///
+    /// Apart from that it behaves like [`Decoder::next`].
+    ///
+    /// # Arguments
+    ///
+    /// * `sample` - The sample value
+    ///
+    /// # Returns
+    ///
+    ///  * None - if no complete datagram is received
+    ///  * Some(datagram) - a completely received and validated datagram
+    ///
+    pub fn next(&mut self, sample: bool) -> Option<Datagram> {
+        let config = &self.decoder.config;
+        let other_sync = match config.sync_on_turning_edge {
+            SyncOnTurningEdge::First => SyncOnTurningEdge::Second,
+            SyncOnTurningEdge::Second => SyncOnTurningEdge::First,
+        };
+        let configured = config.next_ext(&mut self.decoder.state, sample);
+        let other = config.next_ext_synced(&mut self.other, sample, other_sync);
+        match (configured, other) {
+            (DecodeEvent::Datagram(datagram), _) => {
+                config.abort_frame(&mut self.other);
+                self.decoder.buffer_completed(datagram);
+                Some(datagram)
+            }
+            (_, DecodeEvent::Datagram(datagram)) => {
+                config.abort_frame(&mut self.decoder.state);
+                self.decoder.buffer_completed(datagram);
+                Some(datagram)
+            }
+            _ => None,
+        }
+    }
+
+    /// The decoder of the configured interpretation
+    ///
+    /// E.g. to take its completed datagrams via [`Decoder::take_completed`].
+    pub fn decoder(&mut self) -> &mut Decoder {
+        &mut self.decoder
+    }
+}
+
+/// A decoder that additionally logs the sample index of every edge
+///
+/// Samples are counted from the creation of the edge log on. The indices
+/// of the edges of a datagram, from its first edge on, are kept until the
+/// datagram completes and can then be inspected via
+/// [`EdgeLogDecoder::last_frame_edges`], e.g. to correlate the datagram
+/// with other signals. Only the first `N` edges of a datagram are logged.
+///
+/// Created via [`Decoder::with_edge_log`].
+pub struct EdgeLogDecoder<const N: usize> {
+    decoder: Decoder,
+    sample_index: u32,
+    edges: [u32; N],
+    edge_count: usize,
+    last_frame_edges: [u32; N],
+    last_frame_edge_count: usize,
+}
+
+impl<const N: usize> EdgeLogDecoder<N> {
+    /// Sample a manchester modulated signal and log edges
+    ///
+    /// Apart from logging it behaves like [`Decoder::next`].
+    pub fn next(&mut self, sample: bool) -> Option<Datagram> {
+        let state = &self.decoder.state;
+        if sample != state.previous_sample {
+            if state.edge_distance >= self.decoder.config.no_edge_exit_limit() {
+                // first edge after an idle period
+                self.edge_count = 0;
+            }
+            if self.edge_count < N {
+                self.edges[self.edge_count] = self.sample_index;
+                self.edge_count += 1;
+            }
+        }
+        self.sample_index = self.sample_index.wrapping_add(1);
+
+        let datagram = self.decoder.next(sample);
+        if datagram.is_some() {
+            self.last_frame_edges = self.edges;
+            self.last_frame_edge_count = self.edge_count;
+        }
+        datagram
+    }
+
+    /// The sample indices of the edges of the most recently completed datagram
+    pub fn last_frame_edges(&self) -> &[u32] {
+        &self.last_frame_edges[..self.last_frame_edge_count]
+    }
+}
+
+/// A decoder that reads its samples from an input pin
+///
+/// Lowers the integration barrier: a timer ISR just calls
+/// [`PinDecoder::poll`] once per tick instead of reading the pin itself.
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{ActivityLevel, BitOrder, Decoder, PinDecoder, SyncOnTurningEdge};
+/// # struct Pin;
+/// # impl embedded_hal::digital::v2::InputPin for Pin {
+/// #     type Error = ();
+/// #     fn is_high(&self) -> Result<bool, ()> { Ok(true) }
+/// #     fn is_low(&self) -> Result<bool, ()> { Ok(false) }
+/// # }
+/// # let pin = Pin;
+///
+/// let decoder = Decoder::new(ActivityLevel::Low, SyncOnTurningEdge::First, BitOrder::BigEndian);
+/// let mut receiver = PinDecoder::new(pin, decoder);
+/// // periodically called, e.g. by a timer ISR
+/// if let Some(datagram) = receiver.poll() {
+///     // handle the datagram
+/// }
 /// ```
-
-#[derive(Debug)]
-pub struct Encoder<I> {
-    datagram_iter: I,
-    first_half_bit: bool,
-    last_value: Option<bool>,
+pub struct PinDecoder<P> {
+    pin: P,
+    decoder: Decoder,
 }
 
-impl Encoder<DatagramBigEndianIterator> {
-    /// Create a new Encoder ready to encode the datagram passed along
+impl<P: InputPin> PinDecoder<P> {
+    /// Create a new pin decoder
     ///
     /// # Arguments
     ///
-    /// * `datagram` - the datagram to be encoded
-    pub fn new(d: Datagram) -> Self {
-        let mut datagram_iter = d.into_big_endian_iter();
-        let last_value = datagram_iter.next();
-        Encoder::<DatagramBigEndianIterator> {
-            datagram_iter,
-            first_half_bit: true,
-            last_value,
-        }
+    /// * `pin` - The input pin the manchester modulated signal is read from
+    /// * `decoder` - The decoder that does the actual decoding
+    pub const fn new(pin: P, decoder: Decoder) -> Self {
+        PinDecoder { pin, decoder }
     }
-}
 
-impl Encoder<DatagramLittleEndianIterator> {
-    /// Create a new Encoder ready to encode the datagram passed along
-    ///
-    /// # Arguments
+    /// Read the pin and advance decoding
     ///
-    /// * `datagram` - the datagram to be encoded
-    pub fn new(d: Datagram) -> Self {
-        let mut datagram_iter = d.into_little_endian_iter();
-        let last_value = datagram_iter.next();
-        Encoder::<DatagramLittleEndianIterator> {
-            datagram_iter,
-            first_half_bit: true,
-            last_value,
+    /// Behaves like [`Decoder::next`] with the pin level as sample.
+    pub fn poll(&mut self) -> Option<Datagram> {
+        match self.poll_ext() {
+            DecodeEvent::Datagram(datagram) => Some(datagram),
+            _ => None,
         }
     }
-}
 
-impl<I: Iterator<Item = bool>> Iterator for Encoder<I> {
-    type Item = bool;
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.last_value {
-            Some(bit) => {
-                if self.first_half_bit {
-                    self.first_half_bit = false;
-                    Some(!bit)
-                } else {
-                    self.first_half_bit = true;
-                    self.last_value = self.datagram_iter.next();
-                    Some(bit)
-                }
+    /// Read the pin, advance decoding and report what happened
+    ///
+    /// Behaves like [`Decoder::next_event`] with the pin level as sample.
+    /// If the pin cannot be read, the datagram in progress (if any) is
+    /// dropped and [`DecodeError::PinReadFailed`] is reported.
+    pub fn poll_ext(&mut self) -> DecodeEvent {
+        match self.pin.is_high() {
+            Ok(sample) => self.decoder.next_event(sample),
+            Err(_) => {
+                self.decoder.abort_frame();
+                DecodeEvent::Error(DecodeError::PinReadFailed)
             }
-            None => None,
         }
     }
-}
 
-/// Activity level of the Pin where the infrared receiver is attached to.
-/// It is the opposite level the pin takes if no datagram is transmitted.
-#[derive(PartialEq)]
-pub enum ActivityLevel {
-    High,
-    Low,
+    /// Release the pin and the decoder
+    pub fn release(self) -> (P, Decoder) {
+        (self.pin, self.decoder)
+    }
 }
 
-/// A priori knowledge about the first expected bit of a telegram
+/// A DMA channel that feeds half bits to the PWM without CPU involvement
 ///
-/// It is needed for correct decoding if the datagram length is unknown
-pub enum SyncOnTurningEdge {
-    First,
-    Second,
-}
+/// To be implemented for the hardware at hand, e.g. by a DMA transferring
+/// duty values to the PWM on every half bit timer event.
+pub trait HalfBitDma {
+    /// Start emitting the half bits (`true` is carrier on)
+    fn start(&mut self, half_bits: &[bool]);
 
-/// Decode a Manchester encoded stream of periodically taken samples into
-/// a datagram.
-pub struct Decoder {
-    // Config data
-    activity_level: ActivityLevel,
-    sync_on_turning_edge: SyncOnTurningEdge,
-    bit_order: BitOrder,
-    // Collected output data
-    datagram: Datagram,
-    // Internal processing control data
-    previous_sample: bool,
-    edge_distance: u8,
-    recording_distance: u8,
-    receiving_started: bool,
-    record_marker_reached: bool,
+    /// Number of half bits of the current transmission not yet emitted
+    fn remaining(&self) -> usize;
 }
 
-const SAMPLES_PER_HALF_BIT_PERIOD: u8 = 3;
-const TOLERANCE: u8 = 1;
-
-//   ___---___------   e - first edge
-//   xxx012345678901   x - exit criteria no bits are send anymore
-//     f----tttt--xxx  t - tolerance range an edge is expected
-
-const LOWER_BARRIER: u8 = 2 * SAMPLES_PER_HALF_BIT_PERIOD - TOLERANCE;
-const UPPER_BARRIER: u8 = 2 * SAMPLES_PER_HALF_BIT_PERIOD + TOLERANCE;
-const NO_EDGE_EXIT_LIMIT: u8 = 3 * SAMPLES_PER_HALF_BIT_PERIOD;
+/// Send precomputed half bit buffers via DMA
+///
+/// An alternative to the per half bit ISR of [`InfraredEmitter`]: the
+/// datagram is rendered once (see [`Encoder::render_into`]) and handed to
+/// the DMA. The application polls for completion to re-arm. Pauses between
+/// datagrams are up to the application.
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{Datagram, DatagramBigEndianIterator, DmaEmitter, Encoder, HalfBitDma};
+/// # struct Dma;
+/// # impl HalfBitDma for Dma {
+/// #     fn start(&mut self, _half_bits: &[bool]) {}
+/// #     fn remaining(&self) -> usize { 0 }
+/// # }
+/// # let dma = Dma;
+///
+/// let mut half_bits = [false; 28];
+/// let encoder = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("11_0_10100_011111"));
+/// let count = encoder.render_into(&mut half_bits).unwrap();
+///
+/// let mut emitter = DmaEmitter::new(dma);
+/// assert!(emitter.arm_from_buffer(&half_bits[..count]));
+/// while !emitter.is_transmission_complete() {}
+/// ```
+pub struct DmaEmitter<D> {
+    dma: D,
+}
 
-impl Decoder {
-    /// Create an instance of a new manchester encoder
+impl<D: HalfBitDma> DmaEmitter<D> {
+    /// Create a new DMA emitter
     ///
     /// # Arguments
     ///
-    /// * `activity_level` - Low and High indicate what the activity level is
-    ///                      the negation of the activity level is the inactivity
-    ///                      level where no datagram is transmitted.
-    /// * `sync_on_turning_edge` - Indication if on the First or the Second
-    ///                            edge bits are aligned.
-    /// * `bit_order` - Either BigEndian (MSP is received first) or
-    ///                 LittleEndian (LSB is received first)
-    /// In combination of activity_level and sync_on_turning_edge it is determined
-    /// what if the first bit is either zero or one
-    ///
-    /// | Activity level | Sync on turning edge | Resulting first bit datagram |
-    /// |----------------|----------------------|------------------------------|
-    /// | High           | First                | Zero                         |
-    /// | High           | Second               | One                          |
-    /// | Low            | First                | One                          |
-    /// | Low            | Second               | Zero                         |
-    ///
-    pub const fn new(
-        activity_level: ActivityLevel,
-        sync_on_turning_edge: SyncOnTurningEdge,
-        bit_order: BitOrder,
-    ) -> Self {
-        let previous_sample = match activity_level {
-            ActivityLevel::High => false,
-            ActivityLevel::Low => true,
-        };
-        Decoder {
-            datagram: Datagram {
-                buffer: 0,
-                length_in_bit: 0,
-            },
-            previous_sample,
-            edge_distance: NO_EDGE_EXIT_LIMIT,
-            recording_distance: NO_EDGE_EXIT_LIMIT,
-            receiving_started: false,
-            activity_level,
-            sync_on_turning_edge,
-            record_marker_reached: false,
-            bit_order,
-        }
+    /// * `dma` - the DMA channel feeding the PWM
+    pub const fn new(dma: D) -> Self {
+        DmaEmitter { dma }
     }
 
-    /// Sample a manchester modulated signal periodically and extract datagrams
-    ///
-    /// To cover some jitter the sampling rate is three times the half bit frequency
-    /// i.e. an Infrared manchester decoded bit lasts  2x  889 us),
-    /// Thus sampling period should be 296 us.
-    ///
-    /// Note: three times the bit frequency is good enough to consider the Nyquist
-    /// criterion and some potential jitter in sending frequency.
+    /// Check if the previously armed transmission is completely emitted
+    pub fn is_transmission_complete(&self) -> bool {
+        self.dma.remaining() == 0
+    }
+
+    /// Set up the next transmission if the previous one is complete
     ///
     /// # Arguments
     ///
-    ///  * `sample` - the level of the pin true equals high, false equals low
+    /// * `half_bits` - the rendered half bits to be emitted
     ///
     /// # Returns
     ///
-    ///  Option of an infrared datagram
-    ///
-    ///  * None - if no complete datagram is received
-    ///  * Some(datagram) - a completely received datagram
-    ///
-    pub fn next(&mut self, sample: bool) -> Option<Datagram> {
-        // To understand the algorithm record marker are introduced.
-        //
-        // Record marker are the sample taken directly after the edge
-        // in the middle of the transmission of a bit
-        // Record marker are aligned to SyncOnTurningEdge.
-        //
-        // As of the manchester protocol definition, there must be always
-        // an edge in the middle of transmission of the bit.
-        //
-        // Example: Start with "1" (high inactivity)
-        //
-        //        |bit_1|bit_0|bit_0     - The bits
-        // -------...------...---...     - The signal
-        //           ^     ^     ^       - The record marker
-        //
-        // Example: Start with "0" (high inactivity)
-        //
-        //        |bit_0|bit_1|bit_1     - The bits
-        // ----------......---...---     - The signal
-        //           ^     ^     ^       - The record marker
-        //
-        // At each record marker the bit value is determined and recorded
-        let mut return_value: Option<Datagram> = None;
-
-        if sample != self.previous_sample {
-            if !self.receiving_started {
-                // cover the start of the telegram
-                match self.sync_on_turning_edge {
-                    SyncOnTurningEdge::First => {
-                        // first edge is the record marker
-                        self.record_marker_reached = true;
-                        self.receiving_started = true;
-                    }
-                    SyncOnTurningEdge::Second => {
-                        // by protocol design it is guaranteed that there is a second edge
-                        // within half-bit time aka within SAMPLES_PER_HALF_BIT_PERIOD
-                        if self.edge_distance <= SAMPLES_PER_HALF_BIT_PERIOD + TOLERANCE {
-                            // first edge at the record marker
-                            self.record_marker_reached = true;
-                            self.receiving_started = true;
-                        } else {
-                            // very first edge -> do nothing on purpose
-                        }
-                    }
-                }
-            }
-            if self.recording_distance >= LOWER_BARRIER && self.recording_distance <= UPPER_BARRIER
-            {
-                self.record_marker_reached = true;
-            }
-            if self.record_marker_reached {
-                // In the middle of a bit transmission the value is derived from the new sample
-                self.datagram
-                    .add_bit(!sample, self.bit_order) // the sample is NOT mixed with activity_level
-                    .unwrap();
-                // reset internal data for the next record_marker
-                self.recording_distance = 1;
-                self.record_marker_reached = false;
-            }
-            self.previous_sample = sample;
-            self.edge_distance = 1;
+    /// * *true* - if the transmission was started
+    /// * *false* - if the previous transmission is still in progress
+    pub fn arm_from_buffer(&mut self, half_bits: &[bool]) -> bool {
+        if self.is_transmission_complete() {
+            self.dma.start(half_bits);
+            true
         } else {
-            self.edge_distance += 1;
-            self.recording_distance += 1;
+            false
         }
+    }
 
-        if self.edge_distance > NO_EDGE_EXIT_LIMIT {
-            // end of datagram condition no edge anymore
-            if !self.datagram.is_empty() && (sample ^ (self.activity_level == ActivityLevel::High))
-            {
-                return_value = Some(self.datagram);
-                self.receiving_started = false;
-            }
-            self.datagram = Datagram::default();
-            self.edge_distance -= 1; // prevent number overflow
-        }
-        if self.recording_distance > NO_EDGE_EXIT_LIMIT {
-            self.recording_distance -= 1; // prevent number overflow
-        }
-        return_value
+    /// Release the DMA channel
+    pub fn release(self) -> D {
+        self.dma
     }
 }
 
@@ -660,7 +4380,7 @@ impl Decoder {
 ///
 ///     let datagram = Datagram::new("0101_0011_0111_0001");
 ///     defmt::println!("Send new datagram {}", datagram);
-///     infrared_emitter.send_if_possible(datagram, 25);
+///     infrared_emitter.send_if_possible(datagram, BitOrder::BigEndian, 25);
 ///
 ///     loop {
 ///         infrared_emitter.send_half_bit();
@@ -668,43 +4388,191 @@ impl Decoder {
 ///     }
 /// }
 /// ```
-#[derive(Debug)]
-pub struct InfraredEmitter<P, C, I> {
-    encoder: Option<Encoder<I>>,
+pub struct InfraredEmitter<P: Pwm, C> {
+    encoder: Option<Encoder<DatagramEitherIterator>>,
+    // Bit order of the datagram(s) in progress
+    bit_order: BitOrder,
+    // Datagrams to be sent alternately: next, after next, remaining count
+    alternating: Option<(Datagram, Datagram, u8)>,
+    // Datagrams to be sent back-to-back with their bit order and sending power
+    queue: Deque<(Datagram, BitOrder, P::Duty), EMITTER_QUEUE_LEN>,
     max_pause_cycles: u8,
     current_pause_cycles: u8,
     pwm: P,
     channel: C,
+    stats: EmitterStats,
+}
+
+/// Number of datagrams an [`InfraredEmitter`] queues (see [`InfraredEmitter::queue`])
+pub const EMITTER_QUEUE_LEN: usize = 4;
+
+impl<P, C> core::fmt::Debug for InfraredEmitter<P, C>
+where
+    P: Pwm + core::fmt::Debug,
+    P::Duty: core::fmt::Debug,
+    C: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("InfraredEmitter")
+            .field("encoder", &self.encoder)
+            .field("bit_order", &self.bit_order)
+            .field("alternating", &self.alternating)
+            .field("queue", &self.queue)
+            .field("max_pause_cycles", &self.max_pause_cycles)
+            .field("current_pause_cycles", &self.current_pause_cycles)
+            .field("pwm", &self.pwm)
+            .field("channel", &self.channel)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+/// Transmission statistics of an [`InfraredEmitter`]
+///
+/// All counters saturate at their maximum.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EmitterStats {
+    /// Datagrams completely emitted
+    pub frames_sent: u32,
+    /// Half bits emitted
+    pub half_bits_emitted: u32,
+    /// Pause periods enforced after a datagram
+    pub pauses: u32,
 }
 
-impl<P, C, D, I> InfraredEmitter<P, C, I>
+impl<P, C> InfraredEmitter<P, C>
 where
-    P: Pwm + Pwm<Channel = C> + Pwm<Duty = D>,
+    P: Pwm + Pwm<Channel = C>,
     C: Copy,
-    D: core::ops::Mul<Output = D> + core::ops::Div<Output = D>,
-    I: Iterator<Item = bool>,
 {
     /// Create a new infrared Emitter
     ///
     /// # Arguments
     ///
     /// * `pause_cycles` - configures the time between subsequent datagram
-    ///                    emissions. The total duration is half-bit-time (889 µs)
-    ///                    times number of pause bit cycles. In the pause time
-    ///                    no infrared radiation is emitted and other
-    ///                    participants can occupy the radiation space.
+    ///   emissions. The total duration is half-bit-time (889 µs)
+    ///   times number of pause bit cycles. In the pause time
+    ///   no infrared radiation is emitted and other
+    ///   participants can occupy the radiation space.
     /// * `pwm` - the PWM to be used for ir pulse emission
     /// * `channel` - the channel to be used by the PWM
     pub fn new(pause_cycles: u8, pwm: P, channel: C) -> Self {
         InfraredEmitter {
             encoder: None,
+            bit_order: BitOrder::BigEndian,
+            alternating: None,
+            queue: Deque::new(),
             max_pause_cycles: pause_cycles,
             current_pause_cycles: 0,
             pwm,
             channel,
+            stats: EmitterStats::default(),
+        }
+    }
+
+    /// Start encoding a datagram
+    fn start(&mut self, datagram: Datagram, bit_order: BitOrder) {
+        self.bit_order = bit_order;
+        self.encoder = Some(Encoder::with_bit_order(datagram, bit_order));
+    }
+
+    /// Queue a datagram to be sent after the datagrams in progress
+    ///
+    /// Queued datagrams are sent in order by [`InfraredEmitter::send_half_bit`],
+    /// each after the configured pause. Up to [`EMITTER_QUEUE_LEN`]
+    /// datagrams are queued.
+    ///
+    /// # Arguments
+    ///
+    /// * `datagram` - The datagram to be send
+    /// * `bit_order` - The order the bits of the datagram are sent in
+    /// * `sending_power` - The duty cycle of the pwm in percent
+    ///   (see [`InfraredEmitter::send_if_possible`])
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - if the datagram is queued
+    /// * `Err(datagram)` - the rejected datagram if the queue is full
+    pub fn queue(
+        &mut self,
+        datagram: Datagram,
+        bit_order: BitOrder,
+        sending_power: P::Duty,
+    ) -> Result<(), Datagram> {
+        self.queue
+            .push_back((datagram, bit_order, sending_power))
+            .map_err(|(datagram, _, _)| datagram)
+    }
+
+    /// Start the next datagram of an alternating sequence (if any)
+    fn continue_alternating(&mut self) {
+        if let Some((next, after_next, remaining)) = self.alternating {
+            if remaining == 0 {
+                self.alternating = None;
+            } else {
+                self.start(next, self.bit_order);
+                self.alternating = Some((after_next, next, remaining - 1));
+            }
+        }
+    }
+
+    /// A datagram is being sent
+    pub fn is_sending(&self) -> bool {
+        self.encoder.is_some()
+    }
+
+    /// No datagram is being sent, but the pause after a datagram is not over
+    pub fn is_in_pause(&self) -> bool {
+        self.encoder.is_none() && self.current_pause_cycles < self.max_pause_cycles
+    }
+
+    /// Transmission statistics since creation or the last reset
+    pub fn stats(&self) -> EmitterStats {
+        self.stats
+    }
+
+    /// Reset all transmission statistics to zero
+    pub fn reset_stats(&mut self) {
+        self.stats = EmitterStats::default();
+    }
+
+    /// Change the PWM channel used for subsequent datagrams
+    ///
+    /// Allows to drive several infrared LEDs (e.g. pointing in different
+    /// directions) from a single PWM peripheral. The previously used channel
+    /// is disabled. Switching is refused while a datagram is being sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - the channel to be used by the PWM from now on
+    ///
+    /// # Returns
+    ///
+    /// * *true* - if the channel was changed
+    /// * *false* - if a datagram is being sent
+    pub fn set_channel(&mut self, channel: C) -> bool {
+        if self.encoder.is_some() {
+            false
+        } else {
+            self.pwm.disable(self.channel);
+            self.channel = channel;
+            true
         }
     }
+}
 
+impl<P, C, D> InfraredEmitter<P, C>
+where
+    P: Pwm + Pwm<Channel = C> + Pwm<Duty = D>,
+    C: Copy,
+    D: Copy
+        + PartialOrd
+        + From<u8>
+        + core::ops::Add<Output = D>
+        + core::ops::Mul<Output = D>
+        + core::ops::Div<Output = D>
+        + core::ops::Rem<Output = D>,
+{
     /// Progress on sending a datagram by emitting a half bit
     ///
     /// This function needs to be called every half-bit period, i.e. each 889 µs.
@@ -712,6 +4580,9 @@ where
     ///
     /// half-bit emitting happens by enabling/disabling a a properly configured
     /// PWM.
+    ///
+    /// The pause counter saturates, so the emitter may stay idle for any
+    /// number of calls without overflowing it.
     pub fn send_half_bit(&mut self) {
         match &mut self.encoder {
             Some(encoder) => match encoder.next() {
@@ -721,93 +4592,133 @@ where
                     } else {
                         self.pwm.disable(self.channel);
                     }
+                    self.stats.half_bits_emitted = self.stats.half_bits_emitted.saturating_add(1);
                 }
                 None => {
                     self.pwm.disable(self.channel);
                     self.encoder = None;
                     self.current_pause_cycles = 0;
+                    self.stats.frames_sent = self.stats.frames_sent.saturating_add(1);
+                    if self.max_pause_cycles > 0 {
+                        self.stats.pauses = self.stats.pauses.saturating_add(1);
+                    }
                 }
             },
             None => {
                 // the pwm is already disabled -> manage pause period
-                self.current_pause_cycles += 1;
+                self.current_pause_cycles = self.current_pause_cycles.saturating_add(1);
+                if self.current_pause_cycles >= self.max_pause_cycles {
+                    self.continue_alternating();
+                    if self.encoder.is_none() {
+                        if let Some((datagram, bit_order, sending_power)) = self.queue.pop_front() {
+                            self.set_sending_power(sending_power);
+                            self.start(datagram, bit_order);
+                        }
+                    }
+                }
             }
         }
     }
-}
 
-impl<P, C, D> InfraredEmitter<P, C, DatagramBigEndianIterator>
-where
-    P: Pwm + Pwm<Channel = C> + Pwm<Duty = D>,
-    C: Copy,
-    D: core::ops::Mul<Output = D> + core::ops::Div<Output = D>,
-{
     /// Immediately start sending a datagram if possible
     ///
-    /// Sending is possible iff there is no sending procedure in progress.
-    /// A call to this function is not blocking
+    /// Sending is possible iff there is no sending procedure in progress,
+    /// i.e. no datagram is in flight, queued or pending as part of
+    /// [`InfraredEmitter::send_alternating`], and the pause after the
+    /// previous datagram is over. A datagram in flight is never truncated
+    /// or replaced. A call to this function is not blocking
     ///
     /// # Arguments
     ///
     /// * `datagram` - The datagram to be send
+    /// * `bit_order` - The order the bits of the datagram are sent in
     /// * `sending_power` - The duty cycle of the pwm in percent
-    ///                     should be less than or equal 25 (percent)
-    ///                     Is reduced to 25 if a higher value is given.
-    ///                     Lower sending power is appropriate for pairing datagrams.
+    ///   should be less than or equal 25 (percent)
+    ///   Is reduced to 25 if a higher value is given.
+    ///   Lower sending power is appropriate for pairing datagrams.
     ///
     /// # Returns
     ///
     /// * *true* - if sending was initiated
     /// * *false* - if sending was not possible to initiate
-    pub fn send_if_possible(&mut self, datagram: Datagram, sending_power: D) -> bool {
-        if self.current_pause_cycles < self.max_pause_cycles {
+    pub fn send_if_possible(
+        &mut self,
+        datagram: Datagram,
+        bit_order: BitOrder,
+        sending_power: D,
+    ) -> bool {
+        if self.encoder.is_some()
+            || self.alternating.is_some()
+            || !self.queue.is_empty()
+            || self.current_pause_cycles < self.max_pause_cycles
+        {
             false
         } else {
-            // let mut sending_power: D = if sending_power > 25 { 25 } else { sending_power };
-            // let duty = self.pwm.get_max_duty() * sending_power / 100;
-            // self.pwm.set_duty(self.channel, duty);
-            self.pwm.set_duty(self.channel, sending_power);
-            self.encoder = Some(Encoder::<DatagramBigEndianIterator>::new(datagram));
+            self.set_sending_power(sending_power);
+            self.start(datagram, bit_order);
             true
         }
     }
-}
 
-impl<P, C, D> InfraredEmitter<P, C, DatagramLittleEndianIterator>
-where
-    P: Pwm + Pwm<Channel = C> + Pwm<Duty = D>,
-    C: Copy,
-    D: core::ops::Mul<Output = D> + core::ops::Div<Output = D>,
-{
-    /// Immediately start sending a datagram if possible
+    /// Immediately start sending two datagrams alternately if possible
     ///
-    /// Sending is possible iff there is no sending procedure in progress.
-    /// A call to this function is not blocking
+    /// The datagrams are sent in the order `a`, `b`, `a`, `b`, ... with the
+    /// configured pause in between, e.g. for protocols that invert a frame
+    /// or toggle a marker bit on every repetition.
+    /// Sending is possible under the same conditions as for
+    /// [`InfraredEmitter::send_if_possible`].
     ///
     /// # Arguments
     ///
-    /// * `datagram` - The datagram to be send
+    /// * `a` - The datagram sent first (and third, ...)
+    /// * `b` - The datagram sent second (and fourth, ...)
+    /// * `times` - The total number of datagrams sent
+    /// * `bit_order` - The order the bits of both datagrams are sent in
     /// * `sending_power` - The duty cycle of the pwm in percent
-    ///                     should be less than or equal 25 (percent)
-    ///                     Is reduced to 25 if a higher value is given.
-    ///                     Lower sending power is appropriate for pairing datagrams.
+    ///   (see [`InfraredEmitter::send_if_possible`])
     ///
     /// # Returns
     ///
     /// * *true* - if sending was initiated
     /// * *false* - if sending was not possible to initiate
-    pub fn send_if_possible(&mut self, datagram: Datagram, sending_power: D) -> bool {
-        if self.current_pause_cycles < self.max_pause_cycles {
+    pub fn send_alternating(
+        &mut self,
+        a: Datagram,
+        b: Datagram,
+        times: u8,
+        bit_order: BitOrder,
+        sending_power: D,
+    ) -> bool {
+        if times == 0 || !self.send_if_possible(a, bit_order, sending_power) {
             false
         } else {
-            // let mut sending_power: D = if sending_power > 25 { 25 } else { sending_power };
-            // let duty = self.pwm.get_max_duty() * sending_power / 100;
-            // self.pwm.set_duty(self.channel, duty);
-            self.pwm.set_duty(self.channel, sending_power);
-            self.encoder = Some(Encoder::<DatagramLittleEndianIterator>::new(datagram));
+            self.alternating = Some((b, a, times - 1));
             true
         }
     }
+
+    /// Set the duty of the pwm to a percentage (at most 25) of the max duty
+    fn set_sending_power(&mut self, sending_power: D) {
+        let max_power = D::from(25);
+        let percent = if sending_power > max_power {
+            max_power
+        } else {
+            sending_power
+        };
+        // split the max duty to avoid an overflow of the multiplication
+        let max_duty = self.pwm.get_max_duty();
+        let hundred = D::from(100);
+        let duty = max_duty / hundred * percent + max_duty % hundred * percent / hundred;
+        self.pwm.set_duty(self.channel, duty);
+    }
 }
+
+pub mod nec;
+pub mod rc5;
+pub mod rc6;
+
+#[cfg(any(test, feature = "sim"))]
+pub mod sim;
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,152 @@
+//! # NEC infrared protocol decoder
+//!
+//! NEC uses pulse distance encoding instead of Manchester code. A frame
+//! starts with a leader of 9 ms mark (carrier on) and 4.5 ms space,
+//! followed by 32 bits, LSB first:
+//!
+//! | Byte    | 0       | 1        | 2       | 3        |
+//! |---------|---------|----------|---------|----------|
+//! | Meaning | address | !address | command | !command |
+//!
+//! Each bit is a 562.5 µs mark followed by a space of 562.5 µs for a zero
+//! or 1687.5 µs for a one. A final 562.5 µs mark terminates the frame.
+//!
+//! As long as a key is held, a repeat code of 9 ms mark, 2.25 ms space and
+//! 562.5 µs mark is sent instead of the frame.
+
+use crate::ActivityLevel;
+
+const LEADER_MARK_US: u32 = 9000;
+const LEADER_SPACE_US: u32 = 4500;
+const REPEAT_SPACE_US: u32 = 2250;
+const BIT_MARK_US: u32 = 563;
+const ONE_SPACE_US: u32 = 1688;
+const DATA_BITS: u8 = 32;
+
+/// A decoded NEC transmission
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NecFrame {
+    /// A complete frame, the inverted bytes are verified
+    Command { address: u8, command: u8 },
+    /// The repeat code of a held key
+    Repeat,
+}
+
+/// Position within a transmission
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum NecState {
+    Idle,
+    Leader,
+    Data { bits: u32, count: u8 },
+    RepeatStop,
+}
+
+/// Decode periodically taken samples of a NEC infrared receiver
+///
+/// The API mirrors [`crate::Decoder`], the decoder is fed one sample per
+/// call. The timing is checked with a tolerance of ±25 %.
+pub struct NecDecoder {
+    active_high: bool,
+    sample_period_us: u32,
+    previous_sample: bool,
+    run_samples: u32,
+    state: NecState,
+}
+
+impl NecDecoder {
+    /// Create a NEC decoder
+    ///
+    /// # Arguments
+    ///
+    /// * `activity_level` - The level of the pin while a carrier is received
+    /// * `sample_period_us` - The period between two samples in µs, e.g. 100
+    pub const fn new(activity_level: ActivityLevel, sample_period_us: u32) -> Self {
+        let active_high = matches!(activity_level, ActivityLevel::High);
+        NecDecoder {
+            active_high,
+            sample_period_us,
+            previous_sample: !active_high,
+            run_samples: 0,
+            state: NecState::Idle,
+        }
+    }
+
+    /// Sample the signal of the receiver
+    ///
+    /// # Arguments
+    ///
+    ///  * `sample` - the level of the pin true equals high, false equals low
+    ///
+    /// # Returns
+    ///
+    ///  * `Some(frame)` - at the end of the terminating mark of a frame or
+    ///    a repeat code
+    ///  * `None` - otherwise
+    pub fn next(&mut self, sample: bool) -> Option<NecFrame> {
+        if sample == self.previous_sample {
+            self.run_samples = self.run_samples.saturating_add(1);
+            return None;
+        }
+        let mark = self.previous_sample == self.active_high;
+        let duration_us = self.run_samples.saturating_mul(self.sample_period_us);
+        self.previous_sample = sample;
+        self.run_samples = 1;
+        self.finish_run(mark, duration_us)
+    }
+
+    /// Advance the state with a completed mark or space
+    fn finish_run(&mut self, mark: bool, duration_us: u32) -> Option<NecFrame> {
+        let mut frame = None;
+        self.state = match (self.state, mark) {
+            (NecState::Idle, true) if matches(duration_us, LEADER_MARK_US) => NecState::Leader,
+            (NecState::Leader, false) if matches(duration_us, LEADER_SPACE_US) => {
+                NecState::Data { bits: 0, count: 0 }
+            }
+            (NecState::Leader, false) if matches(duration_us, REPEAT_SPACE_US) => {
+                NecState::RepeatStop
+            }
+            (NecState::Data { bits, count }, true) if matches(duration_us, BIT_MARK_US) => {
+                if count == DATA_BITS {
+                    frame = command(bits);
+                    NecState::Idle
+                } else {
+                    self.state
+                }
+            }
+            (NecState::Data { bits, count }, false) if count < DATA_BITS => {
+                if matches(duration_us, BIT_MARK_US) {
+                    NecState::Data {
+                        bits,
+                        count: count + 1,
+                    }
+                } else if matches(duration_us, ONE_SPACE_US) {
+                    NecState::Data {
+                        bits: bits | 1 << count,
+                        count: count + 1,
+                    }
+                } else {
+                    NecState::Idle
+                }
+            }
+            (NecState::RepeatStop, true) if matches(duration_us, BIT_MARK_US) => {
+                frame = Some(NecFrame::Repeat);
+                NecState::Idle
+            }
+            _ => NecState::Idle,
+        };
+        frame
+    }
+}
+
+/// Check a duration against its nominal value with 25 % tolerance
+fn matches(duration_us: u32, nominal_us: u32) -> bool {
+    let duration = duration_us.saturating_mul(4);
+    nominal_us * 3 <= duration && duration <= nominal_us * 5
+}
+
+/// Extract address and command if the inverted bytes match
+fn command(bits: u32) -> Option<NecFrame> {
+    let [address, inverted_address, command, inverted_command] = bits.to_le_bytes();
+    (address == !inverted_address && command == !inverted_command)
+        .then_some(NecFrame::Command { address, command })
+}
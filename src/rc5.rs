@@ -0,0 +1,216 @@
+//! # RC5 infrared protocol helpers
+//!
+//! RC5 transmits 14 bits big endian at a half bit time of 889 µs:
+//!
+//! | Bits (index) | 13 | 12 | 11     | 10..6   | 5..0    |
+//! |--------------|----|----|--------|---------|---------|
+//! | Meaning      | S1 | S2 | toggle | address | command |
+//!
+//! Both start bits `S1` and `S2` are always set.
+
+use crate::{Datagram, DatagramBigEndianIterator, Encoder};
+
+const RC5_LEN: u8 = 14;
+const START_BITS_MASK: u128 = 0b11 << 12;
+
+/// A well-formed RC5 frame
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rc5Frame {
+    datagram: Datagram,
+}
+
+impl Rc5Frame {
+    /// Assemble an RC5 frame including both start bits
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address, only the lower 5 bits are used
+    /// * `command` - The command, only the lower 6 bits are used
+    /// * `toggle` - The toggle bit
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{rc5::Rc5Frame, Datagram};
+    ///
+    /// let standby = Rc5Frame::new(0, 12, false);
+    /// assert_eq!(Datagram::new("11_0_00000_001100"), standby.datagram());
+    /// ```
+    pub const fn new(address: u8, command: u8, toggle: bool) -> Rc5Frame {
+        let buffer = START_BITS_MASK
+            | (toggle as u128) << 11
+            | ((address & 0x1f) as u128) << 6
+            | (command & 0x3f) as u128;
+        Rc5Frame {
+            datagram: Datagram::from_raw(buffer, RC5_LEN),
+        }
+    }
+
+    /// The complete 14 bit datagram of the frame
+    pub const fn datagram(&self) -> Datagram {
+        self.datagram
+    }
+
+    /// Parse a decoded datagram as RC5 frame
+    ///
+    /// # Returns
+    ///
+    /// * `Some(frame)` - if the datagram is 14 bits long and both start bits are set
+    /// * `None` - if the datagram is no RC5 frame
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use manchester_code::{rc5::Rc5Frame, Datagram};
+    ///
+    /// assert!(Rc5Frame::from_datagram(&Datagram::new("11_0_00000_001100")).is_some());
+    /// assert!(Rc5Frame::from_datagram(&Datagram::new("10_0_00000_001100")).is_none());
+    /// ```
+    pub fn from_datagram(d: &Datagram) -> Option<Rc5Frame> {
+        if d.len() == RC5_LEN && d.buffer & START_BITS_MASK == START_BITS_MASK {
+            Some(Rc5Frame { datagram: *d })
+        } else {
+            None
+        }
+    }
+
+    /// The toggle bit, it changes with every new key press
+    ///
+    /// Repeated frames of a held key have the same toggle bit.
+    pub fn toggle(&self) -> bool {
+        self.datagram[11] == 1
+    }
+
+    /// The 5 bit address, i.e. the kind of device (0 for TV sets)
+    pub fn address(&self) -> u8 {
+        self.datagram.extract_data(6, 11) as u8
+    }
+
+    /// The 6 bit command, e.g. the key pressed
+    pub fn command(&self) -> u8 {
+        self.datagram.extract_data(0, 6) as u8
+    }
+}
+
+/// User provided names of RC5 commands
+///
+/// Keeps the naming data with the application while packaging the lookup.
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{rc5::{CommandTable, Rc5Frame}, Datagram};
+///
+/// const COMMANDS: CommandTable = CommandTable::new(&[(12, "standby"), (16, "volume +")]);
+///
+/// let frame = Rc5Frame::from_datagram(&Datagram::new("11_0_00000_001100")).unwrap();
+/// assert_eq!(Some("standby"), COMMANDS.lookup(&frame));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct CommandTable<'a> {
+    entries: &'a [(u8, &'a str)],
+}
+
+impl<'a> CommandTable<'a> {
+    /// Create a command table
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - pairs of command number and name
+    pub const fn new(entries: &'a [(u8, &'a str)]) -> Self {
+        CommandTable { entries }
+    }
+
+    /// Look up the name of the command of a frame
+    ///
+    /// # Returns
+    ///
+    /// * `Some(name)` - the first name given for the command
+    /// * `None` - if the command is not in the table
+    pub fn lookup(&self, frame: &Rc5Frame) -> Option<&'a str> {
+        let command = frame.command();
+        self.entries
+            .iter()
+            .find(|(number, _)| *number == command)
+            .map(|(_, name)| *name)
+    }
+}
+
+/// Encode RC5 frames and keep track of the toggle bit
+///
+/// Every call of [`Rc5Encoder::press`] flips the toggle bit, while
+/// [`Rc5Encoder::repeat`] sends the toggle bit of the last key press again,
+/// as it is done while a key is held.
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::rc5::Rc5Encoder;
+///
+/// let mut rc5 = Rc5Encoder::new();
+/// let first = rc5.press(0, 12);
+/// let held = rc5.repeat(0, 12);
+/// let second = rc5.press(0, 12);
+/// assert!(first.eq(held));
+/// assert!(!rc5.press(0, 12).eq(second));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Rc5Encoder {
+    toggle: bool,
+}
+
+impl Default for Rc5Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rc5Encoder {
+    /// Create an encoder, the first key press is sent with toggle bit zero
+    pub const fn new() -> Self {
+        Rc5Encoder { toggle: true }
+    }
+
+    /// Create an encoder that continues a known toggle state
+    ///
+    /// # Arguments
+    ///
+    /// * `toggle` - The toggle bit of the last key press
+    pub const fn with_toggle(toggle: bool) -> Self {
+        Rc5Encoder { toggle }
+    }
+
+    /// The toggle bit of the last key press
+    pub const fn toggle(&self) -> bool {
+        self.toggle
+    }
+
+    /// Encode a new key press, the toggle bit is flipped
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address, only the lower 5 bits are used
+    /// * `command` - The command, only the lower 6 bits are used
+    ///
+    /// # Returns
+    ///
+    /// The Manchester encoder yielding the half bits of the frame
+    pub fn press(&mut self, address: u8, command: u8) -> Encoder<DatagramBigEndianIterator> {
+        self.toggle = !self.toggle;
+        self.repeat(address, command)
+    }
+
+    /// Encode the repetition of the last key press, i.e. a held key
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address, only the lower 5 bits are used
+    /// * `command` - The command, only the lower 6 bits are used
+    ///
+    /// # Returns
+    ///
+    /// The Manchester encoder yielding the half bits of the frame
+    pub fn repeat(&self, address: u8, command: u8) -> Encoder<DatagramBigEndianIterator> {
+        Encoder::new(Rc5Frame::new(address, command, self.toggle).datagram())
+    }
+}
@@ -0,0 +1,178 @@
+//! # RC6 infrared protocol decoder
+//!
+//! RC6 is the successor of RC5. Its time unit `t` is 444 µs, a bit lasts
+//! `2t`. Unlike RC5 a one is sent as mark (carrier on) followed by space.
+//! A mode 0 frame consists of
+//!
+//! | Field    | Leader      | Start | Mode  | Trailer | Address | Command |
+//! |----------|-------------|-------|-------|---------|---------|---------|
+//! | Bits     | -           | 1     | 3     | 1       | 8       | 8       |
+//! | Duration | `6t` + `2t` | `2t`  | `6t`  | `4t`    | `16t`   | `16t`   |
+//!
+//! The leader is a mark of `6t` followed by a space of `2t`. The start bit
+//! is always one. The trailer bit is the toggle bit, it is twice as wide as
+//! the other bits. All fields are sent MSB first.
+//!
+//! Other modes than mode 0 have different field lengths, they are rejected.
+
+use crate::ActivityLevel;
+
+const UNIT_US: u32 = 444;
+const LEADER_MARK_UNITS: u32 = 6;
+const LEADER_SPACE_UNITS: u32 = 2;
+// start bit, mode bits, trailer bit, address and command
+const FRAME_UNITS: u8 = 2 + 3 * 2 + 4 + 16 * 2;
+const TRAILER_UNIT: u8 = 8;
+const DATA_UNIT: u8 = 12;
+
+/// A decoded RC6 mode 0 frame
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rc6Frame {
+    /// The trailer bit, it changes with every new key press
+    pub toggle: bool,
+    /// The kind of device
+    pub address: u8,
+    /// The key pressed
+    pub command: u8,
+}
+
+/// Position within a transmission
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Rc6State {
+    Idle,
+    Leader,
+    // the level of the units (half bits) after the leader, the first is the MSB
+    Data { marks: u64, count: u8 },
+}
+
+/// Decode periodically taken samples of an RC6 infrared receiver
+///
+/// The API mirrors [`crate::Decoder`], the decoder is fed one sample per
+/// call. Marks and spaces are measured in units of `t`, a deviation of up
+/// to a third of a unit is tolerated.
+pub struct Rc6Decoder {
+    active_high: bool,
+    sample_period_us: u32,
+    previous_sample: bool,
+    run_samples: u32,
+    state: Rc6State,
+}
+
+impl Rc6Decoder {
+    /// Create an RC6 decoder
+    ///
+    /// # Arguments
+    ///
+    /// * `activity_level` - The level of the pin while a carrier is received
+    /// * `sample_period_us` - The period between two samples in µs, e.g. 100
+    pub const fn new(activity_level: ActivityLevel, sample_period_us: u32) -> Self {
+        let active_high = matches!(activity_level, ActivityLevel::High);
+        Rc6Decoder {
+            active_high,
+            sample_period_us,
+            previous_sample: !active_high,
+            run_samples: 0,
+            state: Rc6State::Idle,
+        }
+    }
+
+    /// Sample the signal of the receiver
+    ///
+    /// # Arguments
+    ///
+    ///  * `sample` - the level of the pin true equals high, false equals low
+    ///
+    /// # Returns
+    ///
+    ///  * `Some(frame)` - at the end of the last bit of a frame
+    ///  * `None` - otherwise
+    pub fn next(&mut self, sample: bool) -> Option<Rc6Frame> {
+        if sample == self.previous_sample {
+            self.run_samples = self.run_samples.saturating_add(1);
+            return self.finish_trailing_space(sample);
+        }
+        let mark = self.previous_sample == self.active_high;
+        let duration_us = self.run_samples.saturating_mul(self.sample_period_us);
+        self.previous_sample = sample;
+        self.run_samples = 1;
+        self.finish_run(mark, duration_us)
+    }
+
+    /// Complete a frame that ends with a space, it merges with the idle line
+    fn finish_trailing_space(&mut self, sample: bool) -> Option<Rc6Frame> {
+        if let Rc6State::Data { marks, count } = self.state {
+            let remaining = (FRAME_UNITS - count) as u32;
+            let duration_us = self.run_samples.saturating_mul(self.sample_period_us);
+            if sample != self.active_high && 2 * duration_us > (2 * remaining + 1) * UNIT_US {
+                self.state = Rc6State::Idle;
+                return frame(marks << remaining);
+            }
+        }
+        None
+    }
+
+    /// Advance the state with a completed mark or space
+    fn finish_run(&mut self, mark: bool, duration_us: u32) -> Option<Rc6Frame> {
+        let mut frame_found = None;
+        let units = units(duration_us);
+        self.state = match (self.state, mark, units) {
+            (Rc6State::Idle, true, Some(LEADER_MARK_UNITS)) => Rc6State::Leader,
+            (Rc6State::Leader, false, Some(LEADER_SPACE_UNITS)) => {
+                Rc6State::Data { marks: 0, count: 0 }
+            }
+            (Rc6State::Data { marks, count }, _, Some(units))
+                if count as u32 + units <= FRAME_UNITS as u32 =>
+            {
+                let mut marks = marks << units;
+                if mark {
+                    marks |= (1 << units) - 1;
+                }
+                let count = count + units as u8;
+                if count == FRAME_UNITS {
+                    frame_found = frame(marks);
+                    Rc6State::Idle
+                } else {
+                    Rc6State::Data { marks, count }
+                }
+            }
+            _ => Rc6State::Idle,
+        };
+        frame_found
+    }
+}
+
+/// Round a duration to units of `t`, if it is close enough to a multiple
+fn units(duration_us: u32) -> Option<u32> {
+    let units = (duration_us + UNIT_US / 2) / UNIT_US;
+    let deviation = duration_us.abs_diff(units * UNIT_US);
+    (units > 0 && 3 * deviation <= UNIT_US).then_some(units)
+}
+
+/// Extract the fields of a mode 0 frame from the levels of its units
+fn frame(marks: u64) -> Option<Rc6Frame> {
+    let unit = |index: u8| marks >> (FRAME_UNITS - 1 - index) & 1 == 1;
+    // a bit of `width` units is a one if its first half is a mark
+    let bit = |first: u8, width: u8| {
+        let half = width / 2;
+        let first_half = (first..first + half).all(|index| unit(index) == unit(first));
+        let second_half = (first + half..first + width).all(|index| unit(index) != unit(first));
+        (first_half && second_half).then_some(unit(first))
+    };
+    let bits = |first_bit: u8, count: u8| {
+        (0..count).try_fold(0_u8, |value, index| {
+            Some(value << 1 | bit(DATA_UNIT + 2 * (first_bit + index), 2)? as u8)
+        })
+    };
+    let start = bit(0, 2)?;
+    let mode = (0..3).try_fold(0_u8, |mode, index| {
+        Some(mode << 1 | bit(2 + 2 * index, 2)? as u8)
+    })?;
+    if !start || mode != 0 {
+        return None;
+    }
+    Some(Rc6Frame {
+        toggle: bit(TRAILER_UNIT, 4)?,
+        address: bits(0, 8)?,
+        command: bits(8, 8)?,
+    })
+}
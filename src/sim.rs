@@ -0,0 +1,467 @@
+//! # Simulation and analysis helpers
+//!
+//! Host side tooling around the decoder and encoder, e.g. for replaying
+//! captures of a logic analyzer or for preparing test data.
+//!
+//! The module is available with the `sim` feature and is always compiled
+//! for the unit tests of the crate.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::{
+    ActivityLevel, BitOrder, Datagram, DecodeMode, Decoder, DecoderBuilder, Encoder,
+    SyncOnTurningEdge, SAMPLES_PER_HALF_BIT_PERIOD, TOLERANCE,
+};
+
+/// Decode a stream of samples and report the time between datagrams
+///
+/// Each completed datagram is paired with the time in µs elapsed since the
+/// previous datagram was completed (or since the start of the samples for
+/// the first datagram). This allows timing based repeat detection, e.g.
+/// RC5 repeats a datagram every 114 ms as long as a key is held.
+///
+/// # Arguments
+///
+/// * `decoder` - the decoder used for decoding
+/// * `samples` - the periodically taken samples
+/// * `sample_period_us` - the period between two samples in µs
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{sim, ActivityLevel, BitOrder, Datagram, Decoder, SyncOnTurningEdge};
+///
+/// let mut decoder = Decoder::new(ActivityLevel::Low, SyncOnTurningEdge::First, BitOrder::BigEndian);
+/// let samples = "--------......------...---...----------".bytes().map(|s| s == b'-');
+/// let mut datagrams = sim::decode_with_gaps(&mut decoder, samples, 296);
+/// assert_eq!(Some((Datagram::new("1011"), 39 * 296)), datagrams.next());
+/// assert_eq!(None, datagrams.next());
+/// ```
+pub fn decode_with_gaps<'a, I>(
+    decoder: &'a mut Decoder,
+    samples: I,
+    sample_period_us: u32,
+) -> impl Iterator<Item = (Datagram, u32)> + 'a
+where
+    I: IntoIterator<Item = bool>,
+    I::IntoIter: 'a,
+{
+    let mut elapsed_us = 0_u32;
+    samples.into_iter().filter_map(move |sample| {
+        elapsed_us = elapsed_us.saturating_add(sample_period_us);
+        decoder.next(sample).map(|datagram| {
+            let gap = elapsed_us;
+            elapsed_us = 0;
+            (datagram, gap)
+        })
+    })
+}
+
+/// Run-length encode a stream of samples
+///
+/// Yields `(level, count)` for each run of equal samples, a basic
+/// primitive for eyeballing why a capture does not decode.
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::sim;
+///
+/// let samples = "---...---".bytes().map(|s| s == b'-');
+/// let mut runs = sim::run_lengths(samples);
+/// assert_eq!(Some((true, 3)), runs.next());
+/// assert_eq!(Some((false, 3)), runs.next());
+/// assert_eq!(Some((true, 3)), runs.next());
+/// assert_eq!(None, runs.next());
+/// ```
+pub fn run_lengths<I>(samples: I) -> impl Iterator<Item = (bool, u32)>
+where
+    I: IntoIterator<Item = bool>,
+{
+    let mut samples = samples.into_iter().peekable();
+    core::iter::from_fn(move || {
+        let level = samples.next()?;
+        let mut count = 1_u32;
+        while samples.next_if_eq(&level).is_some() {
+            count = count.saturating_add(1);
+        }
+        Some((level, count))
+    })
+}
+
+/// Compute the fewest leading bits that tell a set of datagrams apart
+///
+/// The leading bits are the first transmitted ones in big endian order.
+/// A datagram shorter than the prefix length contributes all its bits,
+/// i.e. differing lengths also tell datagrams apart.
+///
+/// # Returns
+///
+/// * `Some(len)` - the minimal prefix length such that all datagrams differ within it
+/// * `None` - if two datagrams are identical
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{sim, Datagram};
+///
+/// let commands = [Datagram::new("1100"), Datagram::new("1010"), Datagram::new("0110")];
+/// assert_eq!(Some(2), sim::distinguishing_prefix_len(&commands));
+/// ```
+pub fn distinguishing_prefix_len(datagrams: &[Datagram]) -> Option<u8> {
+    let prefix = |datagram: &Datagram, len: u8| {
+        let len = len.min(datagram.len());
+        let shift = (datagram.len() - len) as u32;
+        (len, datagram.buffer.checked_shr(shift).unwrap_or(0))
+    };
+    let max_len = datagrams.iter().map(Datagram::len).max().unwrap_or(0);
+    (0..=max_len).find(|len| {
+        datagrams.iter().enumerate().all(|(index, datagram)| {
+            datagrams[index + 1..]
+                .iter()
+                .all(|other| prefix(datagram, *len) != prefix(other, *len))
+        })
+    })
+}
+
+/// Compare the datagrams two decoders produce from the same samples
+///
+/// A regression testing tool for decoder changes. Both decoders are fed
+/// every sample. Whenever they disagree, i.e. only one of them completes a
+/// datagram or both complete different ones, the sample index and both
+/// datagrams are reported. A decoder that did not complete a datagram at
+/// that index is represented by an empty datagram.
+///
+/// # Returns
+///
+/// `(sample_index, datagram_a, datagram_b)` of each difference. Reporting
+/// stops if the capacity `N` is exhausted.
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{sim, ActivityLevel, BitOrder, Datagram, Decoder, SyncOnTurningEdge};
+///
+/// let mut a = Decoder::new(ActivityLevel::Low, SyncOnTurningEdge::First, BitOrder::BigEndian);
+/// let mut b = Decoder::new(ActivityLevel::Low, SyncOnTurningEdge::First, BitOrder::LittleEndian);
+/// let samples = "--------......------...---...----------".bytes().map(|s| s == b'-');
+/// let diffs = sim::diff_decode::<_, 4>(&mut a, &mut b, samples);
+/// assert_eq!([(38, Datagram::new("1011"), Datagram::new("1101"))], diffs.as_slice());
+/// ```
+pub fn diff_decode<I, const N: usize>(
+    decoder_a: &mut Decoder,
+    decoder_b: &mut Decoder,
+    samples: I,
+) -> heapless::Vec<(usize, Datagram, Datagram), N>
+where
+    I: IntoIterator<Item = bool>,
+{
+    let mut diffs = heapless::Vec::new();
+    for (index, sample) in samples.into_iter().enumerate() {
+        let a = decoder_a.next(sample);
+        let b = decoder_b.next(sample);
+        if a != b
+            && diffs
+                .push((index, a.unwrap_or_default(), b.unwrap_or_default()))
+                .is_err()
+        {
+            break;
+        }
+    }
+    diffs
+}
+
+/// Render datagrams as initializer of a C array
+///
+/// Eases porting command tables to C firmware. The rendered array is
+/// `static const struct { uint8_t len; uint64_t bits; } name[]` where
+/// `bits` holds the datagram with index 0 as least significant bit.
+///
+/// # Panics
+///
+/// * if a datagram is longer than 64 bits
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{sim, Datagram};
+///
+/// let table = sim::to_c_array(&[Datagram::new("1011")], "commands");
+/// assert!(table.contains("{ 4, 0xbULL },"));
+/// ```
+pub fn to_c_array(datagrams: &[Datagram], name: &str) -> String {
+    let mut c_array = String::new();
+    writeln!(
+        c_array,
+        "static const struct {{ uint8_t len; uint64_t bits; }} {}[] = {{",
+        name
+    )
+    .unwrap();
+    for datagram in datagrams {
+        assert!(datagram.len() <= 64, "Datagram does not fit into uint64_t");
+        writeln!(
+            c_array,
+            "    {{ {}, 0x{:x}ULL }},",
+            datagram.len(),
+            datagram.buffer
+        )
+        .unwrap();
+    }
+    c_array.push_str("};\n");
+    c_array
+}
+
+/// Detect frames sent at a bit rate deviating from the expected one
+///
+/// The samples are split into frames at runs (periods without edge) that
+/// are too long for Manchester code, i.e. longer than two and a half of
+/// the shortest run seen in the frame so far. The shortest run of a frame
+/// is its measured number of samples per half bit. A frame is reported if
+/// this number deviates from `base_samples_per_half_bit` by more than the
+/// default tolerance. This helps to identify captures of several devices.
+///
+/// # Arguments
+///
+/// * `samples` - the periodically taken samples
+/// * `base_samples_per_half_bit` - the expected number of samples per half bit
+///
+/// # Returns
+///
+/// `(sample_index, measured_samples_per_half_bit)` of each deviating frame.
+/// The sample index is the one of the first edge of the frame. Reporting
+/// stops if the capacity `N` is exhausted.
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::sim;
+///
+/// let samples = "------...---......---".bytes().map(|s| s == b'-');
+/// assert!(sim::detect_rate_changes::<_, 4>(samples, 3).is_empty());
+/// ```
+pub fn detect_rate_changes<I, const N: usize>(
+    samples: I,
+    base_samples_per_half_bit: u8,
+) -> heapless::Vec<(usize, u8), N>
+where
+    I: IntoIterator<Item = bool>,
+{
+    let mut deviations = heapless::Vec::new();
+    let mut report = |frame: Option<(usize, Option<usize>)>| {
+        if let Some((start, Some(shortest))) = frame {
+            if shortest.abs_diff(base_samples_per_half_bit as usize) > TOLERANCE as usize {
+                let measured = shortest.min(u8::MAX as usize) as u8;
+                let _ = deviations.push((start, measured));
+            }
+        }
+    };
+    let mut previous_sample = None;
+    let mut last_edge = None;
+    // index of the first edge and shortest run of the current frame
+    let mut frame: Option<(usize, Option<usize>)> = None;
+    for (index, sample) in samples.into_iter().enumerate() {
+        if previous_sample.is_some_and(|previous| previous != sample) {
+            let run = last_edge.map(|last_edge| index - last_edge);
+            frame = match (frame, run) {
+                (Some((start, None)), Some(run)) => Some((start, Some(run))),
+                (Some((start, Some(shortest))), Some(run)) if 2 * run <= 5 * shortest => {
+                    Some((start, Some(shortest.min(run))))
+                }
+                _ => {
+                    report(frame);
+                    Some((index, None))
+                }
+            };
+            last_edge = Some(index);
+        }
+        previous_sample = Some(sample);
+    }
+    report(frame);
+    deviations
+}
+
+/// Conventions of the test vectors created by [`generate_vectors`]
+#[derive(Copy, Clone, Debug)]
+pub struct VectorConfig {
+    /// The mode of the decoder the samples are meant for
+    pub mode: DecodeMode,
+    /// The bit order of the decoder the samples are meant for
+    pub bit_order: BitOrder,
+    /// Oversampling of each half bit
+    pub samples_per_half_bit: u8,
+    /// Number of idle samples before and after each datagram, it must
+    /// exceed three half bits for the end of the datagram to be detected
+    pub idle_samples: usize,
+}
+
+impl VectorConfig {
+    /// Conventions matching [`Decoder::with_mode`], with four half bits idle
+    pub const fn new(mode: DecodeMode, bit_order: BitOrder) -> Self {
+        VectorConfig {
+            mode,
+            bit_order,
+            samples_per_half_bit: SAMPLES_PER_HALF_BIT_PERIOD,
+            idle_samples: 4 * SAMPLES_PER_HALF_BIT_PERIOD as usize,
+        }
+    }
+}
+
+/// Enumerate all datagrams up to a length together with their samples
+///
+/// The first transmitted bit of a datagram is given by the decode mode, so
+/// for every length from 1 to `max_bits` all combinations of the remaining
+/// bits are yielded, i.e. `2^max_bits - 1` vectors in total. Each datagram
+/// is encoded and sampled as it looks to a decoder in the configured
+/// mode, preceded and followed by idle samples.
+///
+/// The number of vectors doubles with every bit: the vectors are created
+/// lazily, but exhausting the iterator is only practical for short
+/// datagrams (e.g. up to about 20 bits).
+///
+/// # Panics
+///
+/// * if `max_bits` exceeds [`Datagram::MAX_BITS`]
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{sim, BitOrder, DecodeMode, Decoder};
+/// use sim::VectorConfig;
+///
+/// let config = VectorConfig::new(DecodeMode::FirstBitOneIdleHigh, BitOrder::BigEndian);
+/// for (datagram, samples) in sim::generate_vectors(config, 4) {
+///     let mut decoder = Decoder::with_mode(config.mode, config.bit_order);
+///     let decoded = samples.into_iter().find_map(|sample| decoder.next(sample));
+///     assert_eq!(Some(datagram), decoded);
+/// }
+/// ```
+pub fn generate_vectors(
+    config: VectorConfig,
+    max_bits: u8,
+) -> impl Iterator<Item = (Datagram, Vec<bool>)> {
+    assert!(
+        max_bits <= Datagram::MAX_BITS,
+        "A datagram holds at most 128 bits"
+    );
+    let first_bit = matches!(
+        config.mode,
+        DecodeMode::FirstBitOneIdleLow | DecodeMode::FirstBitOneIdleHigh
+    ) as u128;
+    (1..=max_bits).flat_map(move |len| {
+        (0..1_u128 << (len - 1)).map(move |remaining_bits| {
+            let buffer = match config.bit_order {
+                BitOrder::BigEndian => first_bit << (len - 1) | remaining_bits,
+                BitOrder::LittleEndian => remaining_bits << 1 | first_bit,
+            };
+            let datagram = Datagram {
+                length_in_bit: len,
+                buffer,
+            };
+            (datagram, vector_samples(&config, datagram))
+        })
+    })
+}
+
+fn vector_samples(config: &VectorConfig, datagram: Datagram) -> Vec<bool> {
+    let (activity_level, _) = config.mode.expand();
+    encoded_samples(
+        datagram,
+        activity_level,
+        config.bit_order,
+        config.samples_per_half_bit,
+        config.idle_samples,
+    )
+}
+
+/// Encode a datagram and sample it as it looks to a decoder
+fn encoded_samples(
+    datagram: Datagram,
+    activity_level: ActivityLevel,
+    bit_order: BitOrder,
+    samples_per_half_bit: u8,
+    idle_samples: usize,
+) -> Vec<bool> {
+    // an active high receiver reads the encoded bits complemented
+    let active_high = activity_level == ActivityLevel::High;
+    let sent = if active_high {
+        Datagram {
+            length_in_bit: datagram.length_in_bit,
+            buffer: !datagram.buffer
+                & u128::MAX
+                    .checked_shr(128 - datagram.length_in_bit as u32)
+                    .unwrap_or(0),
+        }
+    } else {
+        datagram
+    };
+    let idle = core::iter::repeat(!active_high).take(idle_samples);
+    idle.clone()
+        .chain(
+            Encoder::with_bit_order(sent, bit_order).flat_map(|half_bit| {
+                core::iter::repeat(half_bit == active_high).take(samples_per_half_bit as usize)
+            }),
+        )
+        .chain(idle)
+        .collect()
+}
+
+/// Encode a datagram and decode the resulting samples again
+///
+/// Each half bit of the encoder is expanded into `samples_per_half_bit`
+/// samples, preceded and followed by four half bits of idle line. The
+/// decoder uses the default tolerance, reduced if the sampling is too
+/// coarse for it.
+///
+/// # Arguments
+///
+/// * `datagram` - the datagram to be encoded
+/// * `samples_per_half_bit` - oversampling of each half bit
+/// * `activity_level` - the activity level of the receiver
+/// * `sync_on_turning_edge` - the synchronization of the decoder
+/// * `bit_order` - the bit order of encoder and decoder
+///
+/// # Returns
+///
+/// * `Some(datagram)` - the decoded datagram
+/// * `None` - if nothing is decoded or the sampling is not supported by
+///   the decoder
+///
+/// # Example
+///
+/// ```rust
+/// use manchester_code::{sim, ActivityLevel, BitOrder, Datagram, SyncOnTurningEdge};
+///
+/// let datagram = Datagram::new("1011");
+/// let decoded = sim::round_trip(
+///     &datagram,
+///     5,
+///     ActivityLevel::Low,
+///     SyncOnTurningEdge::First,
+///     BitOrder::BigEndian,
+/// );
+/// assert_eq!(Some(datagram), decoded);
+/// ```
+pub fn round_trip(
+    datagram: &Datagram,
+    samples_per_half_bit: u8,
+    activity_level: ActivityLevel,
+    sync_on_turning_edge: SyncOnTurningEdge,
+    bit_order: BitOrder,
+) -> Option<Datagram> {
+    let samples = encoded_samples(
+        *datagram,
+        activity_level,
+        bit_order,
+        samples_per_half_bit,
+        4 * samples_per_half_bit as usize,
+    );
+    let tolerance = TOLERANCE.min(samples_per_half_bit.saturating_sub(1) / 2);
+    let mut decoder = DecoderBuilder::new(activity_level, sync_on_turning_edge, bit_order)
+        .with_samples_per_half_bit(samples_per_half_bit)
+        .with_tolerance(tolerance)
+        .build()
+        .ok()?;
+    samples.into_iter().find_map(|sample| decoder.next(sample))
+}
@@ -14,11 +14,449 @@ mod infrared_datagram {
 
     #[test]
     fn add_bit_datagram_full() {
-        let mut sut = Datagram::default();
-        sut.length_in_bit = 127;
+        let mut sut = Datagram {
+            length_in_bit: 128,
+            ..Default::default()
+        };
         assert!(sut.add_bit(true, BitOrder::LittleEndian).is_err());
     }
 
+    #[test]
+    fn add_bit_fills_all_128_bits() {
+        for order in [BitOrder::BigEndian, BitOrder::LittleEndian] {
+            let mut sut = Datagram::default();
+            for _ in 0..128 {
+                assert!(sut.add_bit(true, order).is_ok());
+            }
+            assert_eq!(128, sut.len());
+            assert_eq!(u128::MAX, sut.buffer);
+            assert!(sut.add_bit(true, order).is_err());
+            assert_eq!(128, sut.len());
+            assert_eq!(u128::MAX, sut.extract_data(0, 128));
+            assert_eq!(1, sut[127]);
+        }
+    }
+
+    #[test]
+    fn full_width_datagram_round_trips_through_iterators() {
+        let datagram = Datagram::from_value_min(1 << 127 | 0x1234_5678);
+        assert_eq!(128, datagram.len());
+        let mut big_endian = Datagram::default();
+        for bit in datagram.into_big_endian_iter() {
+            big_endian.add_bit(bit, BitOrder::BigEndian).unwrap();
+        }
+        assert_eq!(datagram, big_endian);
+        let mut little_endian = Datagram::default();
+        for bit in datagram.into_little_endian_iter() {
+            little_endian.add_bit(bit, BitOrder::LittleEndian).unwrap();
+        }
+        assert_eq!(datagram, little_endian);
+    }
+
+    #[test]
+    fn with_inverted_byte_appended_builds_nec_frame() {
+        let is_valid_nec = |frame: &Datagram| {
+            frame.len() == 32
+                && frame.extract_data(24, 32) ^ frame.extract_data(16, 24) == 0xff
+                && frame.extract_data(8, 16) ^ frame.extract_data(0, 8) == 0xff
+        };
+        let address_and_command = Datagram::new("0001_0010_1110_1101_0011_0100");
+        let sut = address_and_command.with_inverted_byte_appended(0).unwrap();
+        assert_eq!(32, sut.len());
+        assert_eq!(0b1100_1011, sut.extract_data(0, 8));
+        assert_eq!(
+            address_and_command.extract_data(0, 24),
+            sut.extract_data(8, 32)
+        );
+        assert!(is_valid_nec(&sut));
+        assert!(!is_valid_nec(
+            &address_and_command.with_inverted_byte_appended(8).unwrap()
+        ));
+        assert_eq!(None, address_and_command.with_inverted_byte_appended(17));
+        assert_eq!(None, Datagram::default().with_inverted_byte_appended(250));
+    }
+
+    #[test]
+    fn from_bytes_to_bytes_three_byte_payload() {
+        let payload = [0x12, 0x34, 0x56];
+        let sut = Datagram::from_bytes(&payload, BitOrder::BigEndian);
+        assert_eq!(24, sut.len());
+        assert_eq!(0x12_3456, sut.buffer);
+        let mut bytes = [0xff; 4];
+        assert_eq!(3, sut.to_bytes(&mut bytes, BitOrder::BigEndian));
+        assert_eq!(payload, bytes[..3]);
+        assert_eq!(0xff, bytes[3]);
+
+        let sut = Datagram::from_bytes(&payload, BitOrder::LittleEndian);
+        assert_eq!(24, sut.len());
+        assert_eq!(0x56_3412, sut.buffer);
+        let bits: alloc::vec::Vec<bool> = sut.into_little_endian_iter().take(8).collect();
+        assert_eq!(
+            [false, true, false, false, true, false, false, false],
+            bits[..]
+        );
+        let mut bytes = [0xff; 4];
+        assert_eq!(3, sut.to_bytes(&mut bytes, BitOrder::LittleEndian));
+        assert_eq!(payload, bytes[..3]);
+    }
+
+    #[test]
+    fn to_bytes_little_endian_round_trip() {
+        let payload = [0xa5, 0x0f];
+        let sut = Datagram::from_bytes(&payload, BitOrder::LittleEndian);
+        let mut bytes = [0; 2];
+        assert_eq!(2, sut.to_bytes(&mut bytes, BitOrder::LittleEndian));
+        assert_eq!(payload, bytes);
+        // the bits are padded at the end of the transmission
+        let sut = Datagram::from_raw(0b1011, 4);
+        assert_eq!(1, sut.to_bytes(&mut bytes, BitOrder::LittleEndian));
+        assert_eq!(0x0b, bytes[0]);
+    }
+
+    #[test]
+    fn from_bytes_to_bytes_empty() {
+        let sut = Datagram::from_bytes(&[], BitOrder::BigEndian);
+        assert!(sut.is_empty());
+        let mut bytes = [0xff; 2];
+        assert_eq!(0, sut.to_bytes(&mut bytes, BitOrder::BigEndian));
+        assert_eq!([0xff, 0xff], bytes);
+    }
+
+    #[test]
+    fn to_bytes_pads_non_aligned_length() {
+        let sut = Datagram::new("1111_0000_1011");
+        let mut bytes = [0xff; 2];
+        assert_eq!(2, sut.to_bytes(&mut bytes, BitOrder::BigEndian));
+        assert_eq!([0xf0, 0xb0], bytes);
+        let mut short = [0; 1];
+        assert_eq!(1, sut.to_bytes(&mut short, BitOrder::BigEndian));
+        assert_eq!([0xf0], short);
+    }
+
+    #[test]
+    fn reverse_odd_even_and_empty_length() {
+        let sut = Datagram::new("1101_0");
+        assert_eq!(Datagram::new("0101_1"), sut.reverse());
+        let sut = Datagram::new("1100_0000_0001");
+        assert_eq!(Datagram::new("1000_0000_0011"), sut.reverse());
+        assert_eq!(0b1000_0000_0011, sut.reverse().buffer);
+        assert_eq!(sut, sut.reverse().reverse());
+        assert_eq!(Datagram::default(), Datagram::default().reverse());
+        let full = Datagram::from_value_min(1 << 127);
+        assert_eq!(1, full.reverse().buffer);
+    }
+
+    #[test]
+    fn append_four_and_nine_bit_datagrams() {
+        let mut sut = Datagram::new("1011");
+        assert_eq!(Ok(()), sut.append(&Datagram::new("0_0110_1001")));
+        assert_eq!(Datagram::new("1011_0_0110_1001"), sut);
+        assert_eq!(13, sut.len());
+
+        let mut sut = Datagram::new("1011");
+        assert_eq!(Ok(()), sut.push_bits(0b0_0110_1001, 9));
+        assert_eq!(Datagram::new("1011_0_0110_1001"), sut);
+        assert_eq!(Ok(()), sut.push_bits(u128::MAX, 0));
+        assert_eq!(13, sut.len());
+
+        let mut full = Datagram::default();
+        assert_eq!(Ok(()), full.push_bits(u128::MAX, 128));
+        assert_eq!(u128::MAX, full.buffer);
+        assert_eq!(Err(Error), full.append(&Datagram::new("1")));
+        assert_eq!(Err(Error), sut.push_bits(0, 116));
+        assert_eq!(Datagram::new("1011_0_0110_1001"), sut);
+    }
+
+    #[test]
+    fn display_groups_bits_by_four() {
+        let sut = Datagram::new("0-111_10101_00001111");
+        assert_eq!("-0111-1010-1000-0111-1", alloc::format!("{}", sut));
+        assert_eq!("-0111-1010-1000-0111-1", alloc::format!("{:b}", sut));
+        assert_eq!("", alloc::format!("{}", Datagram::default()));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        for sut in [
+            Datagram::default(),
+            Datagram::new("1011"),
+            Datagram::new("0000_0000"),
+            Datagram::from_value_min(u128::MAX),
+        ] {
+            let json = serde_json::to_string(&sut).unwrap();
+            let deserialized: Datagram = serde_json::from_str(&json).unwrap();
+            assert_eq!(sut, deserialized);
+            assert_eq!(sut.len(), deserialized.len());
+        }
+        assert_eq!(
+            "{\"len\":4,\"buffer\":11}",
+            serde_json::to_string(&Datagram::new("1011")).unwrap()
+        );
+        assert!(serde_json::from_str::<Datagram>("{\"len\":2,\"buffer\":11}").is_err());
+        assert!(serde_json::from_str::<Datagram>("{\"len\":129,\"buffer\":0}").is_err());
+    }
+
+    #[test]
+    fn collect_from_bools_and_iterate_back() {
+        let bits = [true, true, false, true, false];
+        let sut: Datagram = bits.iter().copied().collect();
+        assert_eq!(Datagram::new("11010"), sut);
+        let mut iterated = [false; 5];
+        for (index, bit) in sut.into_iter().enumerate() {
+            iterated[index] = bit;
+        }
+        assert_eq!(bits, iterated);
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 128 bits")]
+    fn collect_more_than_128_bits_panics() {
+        let _: Datagram = core::iter::repeat(true).take(129).collect();
+    }
+
+    #[test]
+    fn crc8_known_vectors() {
+        let sut = Datagram::from_bytes(b"123456789", BitOrder::BigEndian);
+        // CRC-8/SMBUS
+        assert_eq!(0xf4, sut.crc8(0x07, 0, 72));
+        // CRC-8/OPENSAFETY
+        assert_eq!(0x3e, sut.crc8(0x2f, 0, 72));
+        // a leading header does not contribute
+        let mut framed = Datagram::new("101");
+        framed.append(&sut).unwrap();
+        assert_eq!(0xf4, framed.crc8(0x07, 0, 72));
+        assert_eq!(0, sut.crc8(0x07, 8, 8));
+    }
+
+    #[test]
+    fn parity_over_seven_bit_field() {
+        // 'a' is 0b110_0001, leading and trailing marker bits
+        let sut = Datagram::new("1_1100001_1");
+        assert!(sut.parity(1, 8));
+        // 'c' is 0b110_0011
+        let sut = Datagram::new("1_1100011_1");
+        assert!(!sut.parity(1, 8));
+        assert!(!sut.parity(3, 3));
+    }
+
+    #[test]
+    fn extract_data_high_offset_ranges() {
+        let sut = Datagram::new("1010_0110_1100_0011_1001_0101");
+        assert_eq!(0b1100_0011_1001_0101, sut.extract_data(0, 16));
+        assert_eq!(0b0011_1001, sut.extract_data(4, 12));
+        assert_eq!(0b1010_0110, sut.extract_data(16, 24));
+        assert_eq!(0b1_1000, sut.extract_data(11, 16));
+        assert_eq!(0b1, sut.extract_data(23, 24));
+        let full = Datagram::from_value_min(1 << 127 | 0xff << 60);
+        assert_eq!(0b1111, full.extract_data(64, 84));
+        assert_eq!(0b1000_0000, full.extract_data(120, 128));
+        assert_eq!(1 << 127 | 0xff << 60, full.extract_data(0, 128));
+        assert_eq!(1, full.extract_data(127, 128));
+    }
+
+    #[test]
+    fn try_extract_data_and_get_at_boundaries() {
+        let sut = Datagram::new("1_0110");
+        assert_eq!(Ok(0b1_0110), sut.try_extract_data(0, 5));
+        assert_eq!(Ok(0b1), sut.try_extract_data(4, 5));
+        assert_eq!(Err(Error), sut.try_extract_data(0, 6));
+        assert_eq!(Err(Error), sut.try_extract_data(3, 3));
+        assert_eq!(Err(Error), sut.try_extract_data(4, 3));
+        assert_eq!(Err(Error), Datagram::default().try_extract_data(0, 1));
+        assert_eq!(Some(0), sut.get(0));
+        assert_eq!(Some(1), sut.get(4));
+        assert_eq!(None, sut.get(5));
+        assert_eq!(None, Datagram::default().get(0));
+    }
+
+    #[test]
+    fn parse_grouped_string() {
+        assert_eq!(
+            Ok(Datagram::new("0111_1010_0001")),
+            Datagram::parse("0-111_1010 \t0001\n")
+        );
+        assert_eq!(Ok(Datagram::default()), Datagram::parse(""));
+    }
+
+    #[test]
+    fn parse_over_long_string() {
+        let mut bits = alloc::string::String::new();
+        for _ in 0..128 {
+            bits.push('1');
+        }
+        assert_eq!(128, Datagram::parse(&bits).unwrap().len());
+        bits.push('0');
+        assert_eq!(Err(ParseError::TooManyBits), Datagram::parse(&bits));
+    }
+
+    #[test]
+    fn parse_illegal_letter() {
+        assert_eq!(
+            Err(ParseError::IllegalCharacter {
+                position: 0,
+                character: 'h'
+            }),
+            Datagram::parse("hello01")
+        );
+        assert_eq!(
+            Err(ParseError::IllegalCharacter {
+                position: 5,
+                character: '2'
+            }),
+            Datagram::parse("01_10211")
+        );
+    }
+
+    /// FNV-1a, good enough to compare hash values in tests
+    struct TestHasher(u64);
+
+    impl core::hash::Hasher for TestHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100_0000_01b3);
+            }
+        }
+    }
+
+    fn hash_of(datagram: &Datagram) -> u64 {
+        use core::hash::{Hash, Hasher};
+        let mut hasher = TestHasher(0xcbf2_9ce4_8422_2325);
+        datagram.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn hash_is_consistent_with_eq() {
+        assert_eq!(
+            hash_of(&Datagram::new("0110")),
+            hash_of(&Datagram::new("01_10"))
+        );
+        assert_ne!(
+            hash_of(&Datagram::new("01")),
+            hash_of(&Datagram::new("001"))
+        );
+        assert_ne!(hash_of(&Datagram::new("01")), hash_of(&Datagram::new("10")));
+    }
+
+    #[test]
+    fn datagrams_deduplicate_in_index_set() {
+        let mut set = heapless::FnvIndexSet::<Datagram, 4>::new();
+        assert_eq!(Ok(true), set.insert(Datagram::new("01")));
+        assert_eq!(Ok(true), set.insert(Datagram::new("001")));
+        assert_eq!(Ok(false), set.insert(Datagram::new("0-1")));
+        assert_eq!(2, set.len());
+    }
+
+    #[test]
+    fn ordering_by_length_then_bits() {
+        let mut datagrams = [
+            Datagram::new("001"),
+            Datagram::new("1"),
+            Datagram::new("10"),
+            Datagram::default(),
+            Datagram::new("01"),
+            Datagram::new("000"),
+        ];
+        datagrams.sort();
+        assert_eq!(
+            [
+                Datagram::default(),
+                Datagram::new("1"),
+                Datagram::new("01"),
+                Datagram::new("10"),
+                Datagram::new("000"),
+                Datagram::new("001"),
+            ],
+            datagrams
+        );
+        assert!(Datagram::new("01") < Datagram::new("001"));
+        assert_ne!(Datagram::new("01"), Datagram::new("001"));
+    }
+
+    const LOOKUP: [Datagram; 3] = [
+        Datagram::from_raw(0, 0),
+        Datagram::from_raw(0b0011_0101, 8),
+        Datagram::from_raw(u128::MAX, 128),
+    ];
+
+    #[test]
+    fn from_raw_equals_new() {
+        assert_eq!(Datagram::default(), LOOKUP[0]);
+        assert_eq!(Datagram::new("0011_0101"), LOOKUP[1]);
+        assert_eq!(128, LOOKUP[2].len());
+        assert_eq!(Datagram::new("1111"), Datagram::from_raw(0b1111, 4));
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn from_raw_with_bits_beyond_length() {
+        let _ = Datagram::from_raw(0b1_0000, 4);
+    }
+
+    #[test]
+    fn leading_zeros_and_significant_bits() {
+        for (bits, leading_zeros) in [("0001", 3), ("1000", 0), ("0000", 4), ("", 0)] {
+            let sut = Datagram::new(bits);
+            assert_eq!(leading_zeros, sut.leading_zeros(), "{}", bits);
+            assert_eq!(
+                sut.len() - leading_zeros,
+                sut.significant_bits(),
+                "{}",
+                bits
+            );
+        }
+        let sut = Datagram::from_raw(1, 128);
+        assert_eq!(127, sut.leading_zeros());
+        assert_eq!(1, sut.significant_bits());
+        assert_eq!(0, Datagram::from_raw(u128::MAX, 128).leading_zeros());
+    }
+
+    #[test]
+    fn from_str_and_try_from() {
+        use core::convert::TryFrom;
+        assert_eq!(Ok(Datagram::new("0101")), "01 01".parse::<Datagram>());
+        assert_eq!(Ok(Datagram::new("0101")), Datagram::try_from("01-01"));
+        assert_eq!(
+            Err(ParseError::IllegalCharacter {
+                position: 1,
+                character: 'x'
+            }),
+            "0xb".parse::<Datagram>()
+        );
+        let too_long = alloc::format!("{:0>129}", 1);
+        assert_eq!(Err(ParseError::TooManyBits), too_long.parse::<Datagram>());
+        assert_eq!(
+            Err(ParseError::TooManyBits),
+            Datagram::try_from(too_long.as_str())
+        );
+    }
+
+    #[test]
+    fn matches_ignores_the_length() {
+        let short = Datagram::new("01");
+        let padded = Datagram::new("0001");
+        assert!(short.matches(&padded));
+        assert!(padded.matches(&short));
+        assert_ne!(short, padded);
+        assert!(!short.matches(&Datagram::new("0011")));
+        assert!(Datagram::default().matches(&Datagram::new("000")));
+    }
+
+    #[test]
+    fn matches_mask_compares_selected_bits() {
+        let sut = Datagram::new("1_0110");
+        assert!(sut.matches_mask(&Datagram::new("0110"), 0b1111));
+        assert!(!sut.matches_mask(&Datagram::new("0110"), 0b1_1111));
+        assert!(sut.matches_mask(&Datagram::new("0_0111"), 0b0_1110));
+        assert!(sut.matches_mask(&Datagram::new("1111"), 0));
+    }
+
     #[test]
     fn add_bit_some_bits_big_endian() {
         let mut sut = Datagram::default();
@@ -65,9 +503,10 @@ mod infrared_datagram {
     #[test]
     fn compare() {
         let sut = Datagram::new("111-010");
-        let mut other = Datagram::default();
-        other.length_in_bit = 6;
-        other.buffer = 0b111010;
+        let other = Datagram {
+            length_in_bit: 6,
+            buffer: 0b111010,
+        };
         assert_eq!(sut, other);
     }
 
@@ -94,6 +533,203 @@ mod infrared_datagram {
         assert_eq!(0b10011, sut.extract_data(0, 6));
     }
 
+    #[test]
+    fn from_value_min() {
+        let sut = Datagram::from_value_min(0b1011);
+        assert_eq!(4, sut.len());
+        assert_eq!(Datagram::new("1011"), sut);
+
+        let sut = Datagram::from_value_min(0);
+        assert_eq!(1, sut.len());
+        assert_eq!(0, sut[0]);
+
+        let sut = Datagram::from_value_min(1 << 127 | 1);
+        assert_eq!(128, sut.len());
+        assert_eq!(1, sut[127]);
+        assert_eq!(1, sut[0]);
+    }
+
+    #[test]
+    fn alternating() {
+        assert_eq!(Datagram::new("1010"), Datagram::alternating(4, true));
+        assert_eq!(Datagram::new("01010"), Datagram::alternating(5, false));
+        assert_eq!(Datagram::default(), Datagram::alternating(0, true));
+    }
+
+    #[test]
+    fn repeated_byte() {
+        assert_eq!(
+            Some(Datagram::new("0011_1100-0011_1100-0011_1100")),
+            Datagram::repeated_byte(0x3c, 3)
+        );
+        assert_eq!(Some(Datagram::default()), Datagram::repeated_byte(0x3c, 0));
+        assert_eq!(None, Datagram::repeated_byte(0x3c, 17));
+    }
+
+    #[test]
+    fn extract_signed() {
+        let sut = Datagram::new("1110_0110");
+        assert_eq!(-2, sut.extract_signed(4, 8));
+        assert_eq!(6, sut.extract_signed(0, 4));
+        assert_eq!(-1, sut.extract_signed(7, 8));
+        assert_eq!(0b1110_0110 - 256, sut.extract_signed(0, 8));
+    }
+
+    #[test]
+    fn sample_count() {
+        let sut = Datagram::new("1011");
+        assert_eq!(24, sut.sample_count(3, 0, 0));
+        assert_eq!(48, sut.sample_count(6, 0, 0));
+        assert_eq!(35, sut.sample_count(3, 2, 9));
+        assert_eq!(66, sut.sample_count(6, 6, 12));
+        assert_eq!(5, Datagram::default().sample_count(3, 2, 3));
+    }
+
+    #[test]
+    fn eq_ignoring_trailing_pad() {
+        let sut = Datagram::new("1011");
+        assert!(sut.eq_ignoring_trailing_pad(&Datagram::new("1011_0000")));
+        assert!(Datagram::new("1011_0000").eq_ignoring_trailing_pad(&sut));
+        assert!(sut.eq_ignoring_trailing_pad(&sut));
+        assert!(!sut.eq_ignoring_trailing_pad(&Datagram::new("1011_0001")));
+        assert!(!sut.eq_ignoring_trailing_pad(&Datagram::new("0001_0110")));
+    }
+
+    #[test]
+    fn value_in_mask() {
+        let allowed = 1 << 1 | 1 << 16 | 1 << 63;
+        assert!(Datagram::new("11_0_00000_010000").value_in_mask(0, 6, allowed));
+        assert!(!Datagram::new("11_0_00000_010001").value_in_mask(0, 6, allowed));
+        assert!(Datagram::new("11_0_00000_111111").value_in_mask(0, 6, allowed));
+        assert!(Datagram::new("11_0_00001_000000").value_in_mask(6, 5, allowed));
+        // values beyond 63 are never allowed
+        assert!(!Datagram::new("1_000000").value_in_mask(0, 7, u64::MAX));
+    }
+
+    #[test]
+    fn records() {
+        let sut = Datagram::new("0011_0001_0010_1111");
+        let mut records = sut.records(4, 4);
+        assert_eq!(Some(0b0001), records.next());
+        assert_eq!(Some(0b0010), records.next());
+        assert_eq!(Some(0b1111), records.next());
+        assert_eq!(None, records.next());
+
+        // count exceeds the available records
+        let sut = Datagram::new("0100_0001_0010_1111");
+        assert_eq!(3, sut.records(4, 4).count());
+        // bits after the counted records are ignored
+        let sut = Datagram::new("0001_0001_0010_1111");
+        assert_eq!(1, sut.records(4, 4).count());
+        // too short for the header
+        assert_eq!(0, Datagram::new("11").records(4, 4).count());
+        assert_eq!(0, sut.records(4, 0).count());
+    }
+
+    #[test]
+    fn xor_checksum() {
+        let sut = Datagram::new("0001_0010_0100_1011");
+        assert_eq!(0b1100, sut.xor_checksum(4));
+        assert_eq!(0b0001_0010 ^ 0b0100_1011, sut.xor_checksum(8));
+        assert_eq!(0b0001_0010_0100_1011, sut.xor_checksum(16));
+        assert_eq!(0b0001_0010_0100_1011, sut.xor_checksum(128));
+        assert_eq!(0, sut.xor_checksum(1));
+        assert_eq!(0, sut.xor_checksum(0));
+        assert_eq!(0, Datagram::default().xor_checksum(4));
+    }
+
+    #[test]
+    fn strip_byte_parity() {
+        let sut = Datagram::new("1010_0101_0-0000_0111_1");
+        assert_eq!(
+            Some(Datagram::new("1010_0101-0000_0111")),
+            sut.strip_byte_parity(Parity::Even)
+        );
+        assert_eq!(None, sut.strip_byte_parity(Parity::Odd));
+        let sut = Datagram::new("1010_0101_1-0000_0111_0");
+        assert_eq!(
+            Some(Datagram::new("1010_0101-0000_0111")),
+            sut.strip_byte_parity(Parity::Odd)
+        );
+        // parity failure in the second byte only
+        let sut = Datagram::new("1010_0101_0-0000_0111_0");
+        assert_eq!(None, sut.strip_byte_parity(Parity::Even));
+        assert_eq!(None, sut.strip_byte_parity(Parity::Odd));
+        assert_eq!(
+            None,
+            Datagram::new("1010_0101").strip_byte_parity(Parity::Even)
+        );
+        assert_eq!(
+            Some(Datagram::default()),
+            Datagram::default().strip_byte_parity(Parity::Even)
+        );
+    }
+
+    #[test]
+    fn gray_code() {
+        assert_eq!(Datagram::new("000"), Datagram::new("000").binary_to_gray());
+        assert_eq!(Datagram::new("110"), Datagram::new("100").binary_to_gray());
+        assert_eq!(Datagram::new("100"), Datagram::new("111").binary_to_gray());
+        assert_eq!(Datagram::new("111"), Datagram::new("100").gray_to_binary());
+        for value in [0_u128, 1, 0b1011, 0xdead_beef, u128::MAX] {
+            let sut = Datagram::from_value_min(value);
+            assert_eq!(sut, sut.binary_to_gray().gray_to_binary());
+            assert_eq!(sut, sut.gray_to_binary().binary_to_gray());
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let schema = Schema::new()
+            .required_len(14)
+            .fixed_bits(0b11 << 12, 0b11 << 12)
+            .field_range(0, 6, 0, 31)
+            .field_range(6, 5, 0, 20);
+        assert_eq!(Ok(()), Datagram::new("11_0_10100_011111").validate(&schema));
+        assert_eq!(
+            Err(ValidationError::LengthMismatch),
+            Datagram::new("11_0_10100_01111").validate(&schema)
+        );
+        assert_eq!(
+            Err(ValidationError::FixedBitsMismatch),
+            Datagram::new("10_0_10100_011111").validate(&schema)
+        );
+        assert_eq!(
+            Err(ValidationError::FieldOutOfRange { offset: 0 }),
+            Datagram::new("11_0_10100_100000").validate(&schema)
+        );
+        assert_eq!(
+            Err(ValidationError::FieldOutOfRange { offset: 6 }),
+            Datagram::new("11_0_10101_011111").validate(&schema)
+        );
+        let beyond_length = Schema::new().field_range(10, 6, 0, 63);
+        assert_eq!(
+            Err(ValidationError::FieldOutOfRange { offset: 10 }),
+            Datagram::new("11_0_10100_011111").validate(&beyond_length)
+        );
+        assert_eq!(Ok(()), Datagram::new("1").validate(&Schema::new()));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one bit")]
+    fn field_range_rejects_zero_width() {
+        let _ = Schema::new().field_range(4, 0, 0, 0);
+    }
+
+    #[test]
+    fn to_hex_string_byte_aligned() {
+        let sut = Datagram::new("1010_0101");
+        assert_eq!("a5", sut.to_hex_string::<32>().as_str());
+    }
+
+    #[test]
+    fn to_hex_string_partial_leading_nibble() {
+        let sut = Datagram::new("11-1100_0011_1010");
+        assert_eq!("3c3a", sut.to_hex_string::<32>().as_str());
+        assert_eq!("3c", sut.to_hex_string::<2>().as_str());
+        assert_eq!("", Datagram::default().to_hex_string::<32>().as_str());
+    }
+
     #[test]
     #[should_panic]
     fn range_access_too_big_index() {
@@ -180,12 +816,89 @@ mod datagram_iterator {
         assert_eq!(Some(false), sut.next());
         assert_eq!(None, sut.next());
     }
+
+    #[test]
+    fn len_decreases_while_consuming() {
+        let mut sut = Datagram::new("1101").iter_big_endian();
+        assert_eq!(4, sut.len());
+        sut.next();
+        assert_eq!(3, sut.len());
+        sut.next_back();
+        assert_eq!((2, Some(2)), sut.size_hint());
+        let mut sut = Datagram::new("1101").iter_little_endian();
+        sut.next();
+        assert_eq!(3, sut.len());
+    }
+
+    #[test]
+    fn rev_yields_reverse_sequence() {
+        let datagram = Datagram::new("1101_0");
+        let forward: heapless::Vec<bool, 8> = datagram.iter_big_endian().collect();
+        let mut backward: heapless::Vec<bool, 8> = datagram.iter_big_endian().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        let little_endian: heapless::Vec<bool, 8> = datagram.iter_little_endian().collect();
+        let big_endian_rev: heapless::Vec<bool, 8> = datagram.iter_big_endian().rev().collect();
+        assert_eq!(little_endian, big_endian_rev);
+    }
+
+    #[test]
+    fn next_and_next_back_meet_in_the_middle() {
+        let datagram = Datagram::new("10011");
+        let mut sut = datagram.iter_big_endian();
+        assert_eq!(Some(true), sut.next());
+        assert_eq!(Some(true), sut.next_back());
+        assert_eq!(Some(false), sut.next());
+        assert_eq!(Some(true), sut.next_back());
+        assert_eq!(Some(false), sut.next());
+        assert_eq!(None, sut.next());
+        assert_eq!(None, sut.next_back());
+        assert_eq!(0, sut.len());
+        let mut sut = datagram.iter_little_endian();
+        assert_eq!(Some(true), sut.next());
+        assert_eq!(Some(true), sut.next_back());
+        assert_eq!(Some(true), sut.next());
+        assert_eq!(Some(false), sut.next_back());
+        assert_eq!(Some(false), sut.next_back());
+        assert_eq!(None, sut.next_back());
+        assert_eq!(None, sut.next());
+    }
 }
 
 mod encoder {
 
     use super::*;
 
+    #[test]
+    fn half_bit_count_of_bit_length() {
+        assert_eq!(0, half_bit_count(0));
+        assert_eq!(2, half_bit_count(1));
+        assert_eq!(28, half_bit_count(14));
+        assert_eq!(510, half_bit_count(255));
+    }
+
+    #[test]
+    fn frame_duration_of_bit_length() {
+        assert_eq!(0, frame_duration_us(0, 889));
+        assert_eq!(1778, frame_duration_us(1, 889));
+        assert_eq!(24_892, frame_duration_us(14, 889));
+        assert_eq!(u32::MAX, frame_duration_us(2, u32::MAX));
+    }
+
+    #[test]
+    fn padded_to_fixed_half_bit_count() {
+        let sut = Encoder::<DatagramBigEndianIterator>::padded_to(Datagram::new("01"), 20).unwrap();
+        let half_bits: heapless::Vec<bool, 32> = sut.collect();
+        assert_eq!(20, half_bits.len());
+        assert_eq!([true, false, false, true], half_bits[..4]);
+        assert!(half_bits[4..].iter().all(|half_bit| !half_bit));
+
+        let sut = Encoder::<DatagramBigEndianIterator>::padded_to(Datagram::new("01"), 4);
+        assert_eq!(4, sut.unwrap().count());
+        let sut = Encoder::<DatagramBigEndianIterator>::padded_to(Datagram::new("01"), 3);
+        assert!(sut.is_none());
+    }
+
     #[test]
     fn iterate_empty() {
         let datagram = Datagram::new("");
@@ -240,6 +953,76 @@ mod encoder {
         assert_eq!(None, sut.next());
     }
 
+    #[test]
+    fn carrier_gate_ticks_of_half_bits() {
+        let sut = CarrierGate::new([true, false, true].iter().copied(), 4);
+        #[rustfmt::skip]
+        let expected = [
+            true, false, true, false,
+            false, false, false, false,
+            true, false, true, false,
+        ];
+        assert!(sut.eq(expected.iter().copied()));
+    }
+
+    #[test]
+    fn carrier_gate_with_odd_ratio_restarts_high() {
+        let encoder = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("0"));
+        let sut = CarrierGate::new(encoder, 3);
+        // zero: active first half bit, inactive second half bit
+        let expected = [true, false, true, false, false, false];
+        assert!(sut.eq(expected.iter().copied()));
+        let sut = CarrierGate::new([true, true].iter().copied(), 3);
+        let expected = [true, false, true, true, false, true];
+        assert!(sut.eq(expected.iter().copied()));
+    }
+
+    #[test]
+    fn remaining_half_bits_while_encoding() {
+        let mut sut = Encoder::<DatagramLittleEndianIterator>::new(Datagram::new("1011"));
+        assert_eq!(8, sut.remaining_half_bits());
+        for remaining in (0..8).rev() {
+            assert!(sut.next().is_some());
+            assert_eq!(remaining, sut.remaining_half_bits());
+        }
+        assert_eq!(None, sut.next());
+        assert_eq!(0, sut.remaining_half_bits());
+
+        let mut sut =
+            Encoder::<DatagramBigEndianIterator>::padded_to(Datagram::new("01"), 7).unwrap();
+        assert_eq!(7, sut.remaining_half_bits());
+        sut.nth(4);
+        assert_eq!(2, sut.remaining_half_bits());
+        assert_eq!(
+            0,
+            Encoder::<DatagramBigEndianIterator>::new(Datagram::default()).remaining_half_bits()
+        );
+    }
+
+    #[test]
+    fn load_new_datagram() {
+        let mut sut = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("1"));
+        assert_eq!(Some(false), sut.next());
+        assert_eq!(Some(true), sut.next());
+        assert_eq!(None, sut.next());
+        sut.load(Datagram::new("01"));
+        assert_eq!(Some(true), sut.next());
+        assert_eq!(Some(false), sut.next());
+        assert_eq!(Some(false), sut.next());
+        assert_eq!(Some(true), sut.next());
+        assert_eq!(None, sut.next());
+    }
+
+    #[test]
+    fn load_drops_remaining_half_bits() {
+        let mut sut = Encoder::<DatagramLittleEndianIterator>::new(Datagram::new("0000"));
+        assert_eq!(Some(true), sut.next());
+        sut.load(Datagram::new("1"));
+        assert_eq!(Some(false), sut.next());
+        assert_eq!(Some(true), sut.next());
+        assert_eq!(None, sut.next());
+    }
+
     #[test]
     fn iterate_zero_one_little_endian() {
         let mut sut = Encoder::<DatagramLittleEndianIterator>::new(Datagram::new("01"));
@@ -251,6 +1034,26 @@ mod encoder {
         assert_eq!(Some(false), sut.next());
         assert_eq!(None, sut.next());
     }
+
+    #[test]
+    fn typed_encoders_match_runtime_bit_order() {
+        let datagram = Datagram::new("1101_0001");
+        let half_bits =
+            |encoder: &mut dyn Iterator<Item = bool>| encoder.collect::<alloc::vec::Vec<_>>();
+        assert_eq!(
+            half_bits(&mut Encoder::with_bit_order(datagram, BitOrder::BigEndian)),
+            half_bits(&mut Encoder::<DatagramBigEndianIterator>::new(datagram))
+        );
+        let mut little_endian = Encoder::<DatagramLittleEndianIterator>::new(Datagram::new("1"));
+        little_endian.load(datagram);
+        assert_eq!(
+            half_bits(&mut Encoder::with_bit_order(
+                datagram,
+                BitOrder::LittleEndian
+            )),
+            half_bits(&mut little_endian)
+        );
+    }
 }
 
 mod decoder {
@@ -311,18 +1114,21 @@ mod decoder {
             SyncOnTurningEdge::First,
             BitOrder::LittleEndian,
         );
-        assert_eq!(true, sut.previous_sample);
+        assert!(sut.state.previous_sample);
 
         let sut = Decoder::new(
             ActivityLevel::High,
             SyncOnTurningEdge::First,
             BitOrder::LittleEndian,
         );
-        assert_eq!(false, sut.previous_sample);
+        assert!(!sut.state.previous_sample);
 
-        assert_eq!(NO_EDGE_EXIT_LIMIT, sut.edge_distance);
-        assert_eq!(NO_EDGE_EXIT_LIMIT, sut.recording_distance);
-        assert_eq!(sut.datagram, Datagram::default());
+        assert_eq!(sut.config.no_edge_exit_limit(), sut.state.edge_distance);
+        assert_eq!(
+            sut.config.no_edge_exit_limit(),
+            sut.state.recording_distance
+        );
+        assert_eq!(sut.state.datagram, Datagram::default());
     }
 
     #[test]
@@ -377,32 +1183,193 @@ mod decoder {
         assert_receive_datagram!(&mut sut, '-', "011");
     }
 
-    // tests about activity and edge level
-
     #[test]
-    fn sample_low_active_on_first_edge_datagram_with_one_bit() {
-        let mut sut = Decoder::new(
+    fn sample_with_external_state_datagram_1011() {
+        const SUT: Decoder = Decoder::new(
             ActivityLevel::Low,
             SyncOnTurningEdge::First,
             BitOrder::BigEndian,
         );
-        //           -----+-----+-----+
-        let input = "--------...---------";
-        assert_signal_sampling!(&mut sut, input);
-        assert_receive_datagram!(&mut sut, '-', "1");
+        let mut state = SUT.initial_state();
+        //          -----+-----+-----+-----+-----+-----+
+        let input = "--------......------...---...---------";
+        for sample in input.bytes() {
+            assert!(SUT.next_with_state(&mut state, sample == b'-').is_none());
+        }
+        assert_eq!(
+            Some(Datagram::new("1011")),
+            SUT.next_with_state(&mut state, true)
+        );
     }
 
     #[test]
-    fn sample_low_active_on_second_edge_datagram_with_one_bit() {
+    fn sample_into_producer_reports_overflow() {
+        let mut queue = heapless::spsc::Queue::<Datagram, 3>::new();
+        let (mut producer, mut consumer) = queue.split();
         let mut sut = Decoder::new(
             ActivityLevel::Low,
-            SyncOnTurningEdge::Second,
+            SyncOnTurningEdge::First,
             BitOrder::BigEndian,
         );
-        //           -----+-----+-----+
-        let input = "-----...---------";
-        assert_signal_sampling!(&mut sut, input);
-        assert_receive_datagram!(&mut sut, '-', "0");
+        let frames = [
+            "--------......------...---...----------",
+            "--------...---......---...------------",
+            "--------......---...---...------------",
+        ];
+        let mut results = [Ok(()); 3];
+        for (frame, result) in frames.iter().zip(results.iter_mut()) {
+            for sample in frame.bytes() {
+                let outcome = sut.next_into_producer(sample == b'-', &mut producer);
+                if outcome.is_err() {
+                    *result = outcome;
+                }
+            }
+        }
+        assert_eq!(Ok(()), results[0]);
+        assert_eq!(Ok(()), results[1]);
+        assert_eq!(Err(Datagram::new("1000")), results[2]);
+        assert_eq!(Some(Datagram::new("1011")), consumer.dequeue());
+        assert_eq!(Some(Datagram::new("1100")), consumer.dequeue());
+        assert_eq!(None, consumer.dequeue());
+    }
+
+    #[test]
+    fn take_completed_back_to_back_datagrams() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        assert_eq!(None, sut.take_completed());
+        let input = "--------......------...---...------------...---......---...----------";
+        for sample in input.bytes() {
+            sut.next(sample == b'-');
+        }
+        assert_eq!(Some(Datagram::new("1011")), sut.take_completed());
+        assert_eq!(Some(Datagram::new("1100")), sut.take_completed());
+        assert_eq!(None, sut.take_completed());
+    }
+
+    #[test]
+    fn take_completed_keeps_the_two_most_recent_datagrams() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let input = concat!(
+            "--------......---...----------",
+            "--------......------...---...----------",
+            "--------......------...---......---...----------",
+        );
+        for sample in input.bytes() {
+            sut.next(sample == b'-');
+        }
+        assert_eq!(Some(Datagram::new("1011")), sut.take_completed());
+        assert_eq!(Some(Datagram::new("101100")), sut.take_completed());
+        assert_eq!(None, sut.take_completed());
+    }
+
+    #[test]
+    fn decoder_state_fits_in_128_bytes() {
+        assert!(core::mem::size_of::<DecoderState>() <= 128);
+    }
+
+    #[test]
+    fn bench_feed_drops_overlong_datagram() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let mut signal = alloc::string::String::from("--------");
+        for _ in 0..140 {
+            signal.push_str("...---");
+        }
+        signal.push_str("----------......------...---...----------");
+        let samples: alloc::vec::Vec<bool> = signal.bytes().map(|s| s == b'-').collect();
+        assert_eq!(1, sut.bench_feed(&samples));
+        assert_eq!(Some(Datagram::new("1011")), sut.take_completed());
+        assert_eq!(None, sut.take_completed());
+    }
+
+    #[test]
+    fn step_reports_event_and_progress() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let signal = "--------......------...---...----------";
+        let mut steps = signal.bytes().map(|sample| sut.step(sample == b'-'));
+        assert!(steps
+            .by_ref()
+            .take(8)
+            .all(|step| step == (DecodeEvent::Idle, 0)));
+        // the first edge is the first record marker
+        assert_eq!(Some((DecodeEvent::Idle, 1)), steps.next());
+        let steps: heapless::Vec<(DecodeEvent, u8), 32> = steps.collect();
+        assert_eq!((DecodeEvent::Idle, 2), steps[5]);
+        assert_eq!((DecodeEvent::Idle, 4), steps[steps.len() - 2]);
+        assert_eq!(
+            (DecodeEvent::Datagram(Datagram::new("1011")), 0),
+            steps[steps.len() - 1]
+        );
+    }
+
+    #[test]
+    fn expected_next_event_progression() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let mut progression = heapless::Vec::<ExpectedEvent, 16>::new();
+        progression.push(sut.expected_next()).unwrap();
+        let input = "--------......------...---...----------";
+        for sample in input.bytes() {
+            sut.next(sample == b'-');
+            let expected = sut.expected_next();
+            if progression.last() != Some(&expected) {
+                progression.push(expected).unwrap();
+            }
+        }
+        use ExpectedEvent::*;
+        assert_eq!(
+            [
+                FrameStart, Boundary, MidBit, Boundary, MidBit, Boundary, MidBit, Boundary, MidBit,
+                End, FrameStart
+            ],
+            progression.as_slice()
+        );
+    }
+
+    // tests about activity and edge level
+
+    #[test]
+    fn sample_low_active_on_first_edge_datagram_with_one_bit() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        //           -----+-----+-----+
+        let input = "--------...---------";
+        assert_signal_sampling!(&mut sut, input);
+        assert_receive_datagram!(&mut sut, '-', "1");
+    }
+
+    #[test]
+    fn sample_low_active_on_second_edge_datagram_with_one_bit() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::Second,
+            BitOrder::BigEndian,
+        );
+        //           -----+-----+-----+
+        let input = "-----...---------";
+        assert_signal_sampling!(&mut sut, input);
+        assert_receive_datagram!(&mut sut, '-', "0");
     }
 
     #[test]
@@ -435,7 +1402,7 @@ mod decoder {
     fn logic() {
         let sample = false;
         let high_activity = false;
-        assert!(true && (sample ^ !high_activity));
+        assert!(sample ^ !high_activity);
     }
 
     #[test]
@@ -503,4 +1470,2299 @@ mod decoder {
         let input = "------------............";
         assert_signal_sampling!(&mut sut, input);
     }
+
+    #[test]
+    fn validate_timing_default_configuration() {
+        let sut = Decoder::new(
+            ActivityLevel::High,
+            SyncOnTurningEdge::First,
+            BitOrder::LittleEndian,
+        );
+        assert_eq!(Ok(()), sut.validate_timing());
+    }
+
+    #[test]
+    fn receiver_constructors_decode_matching_polarity() {
+        let active_low = "--------......------...---...----------";
+        let active_high: alloc::string::String = active_low
+            .chars()
+            .map(|c| if c == '-' { '.' } else { '-' })
+            .collect();
+        let decode = |mut sut: Decoder, signal: &str| {
+            signal
+                .bytes()
+                .filter_map(|sample| sut.next(sample == b'-'))
+                .next()
+        };
+        let sut = Decoder::for_active_low_receiver(BitOrder::BigEndian);
+        assert_eq!(Some(Datagram::new("1011")), decode(sut, active_low));
+        let sut = Decoder::for_active_high_receiver(BitOrder::BigEndian);
+        assert_eq!(Some(Datagram::new("1011")), decode(sut, &active_high));
+    }
+
+    #[test]
+    fn constructors_infer_first_bit() {
+        let rows = [
+            (ActivityLevel::High, SyncOnTurningEdge::First, false),
+            (ActivityLevel::High, SyncOnTurningEdge::Second, true),
+            (ActivityLevel::Low, SyncOnTurningEdge::First, true),
+            (ActivityLevel::Low, SyncOnTurningEdge::Second, false),
+        ];
+        for (activity_level, sync_on_turning_edge, first_bit) in rows {
+            let sut = Decoder::new(activity_level, sync_on_turning_edge, BitOrder::BigEndian);
+            assert_eq!(first_bit, sut.inferred_first_bit());
+        }
+        let sut = Decoder::for_active_low_receiver(BitOrder::BigEndian);
+        assert!(sut.inferred_first_bit());
+        let mut sut = Decoder::for_active_high_receiver(BitOrder::BigEndian);
+        assert!(sut.inferred_first_bit());
+        let mut received = "........------......---...---..........."
+            .bytes()
+            .filter_map(|sample| sut.next(sample == b'-'));
+        assert_eq!(Some(Datagram::new("1011")), received.next());
+    }
+
+    #[test]
+    fn inverted_bit_sense_is_independent_of_convention() {
+        let signal = "........------......---...---...........";
+        let builder = || {
+            DecoderBuilder::new(
+                ActivityLevel::High,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            )
+            .with_inverted_bit_sense(true)
+        };
+        let decode = |mut sut: Decoder| signal.bytes().find_map(|sample| sut.next(sample == b'-'));
+        let sut = builder()
+            .with_convention(Convention::GeThomas)
+            .build()
+            .unwrap();
+        assert!(sut.inferred_first_bit());
+        assert_eq!(Some(Datagram::new("1011")), decode(sut));
+        let sut = builder()
+            .with_convention(Convention::Ieee8023)
+            .build()
+            .unwrap();
+        assert!(!sut.inferred_first_bit());
+        assert_eq!(Some(Datagram::new("0100")), decode(sut));
+        let sut = Decoder::for_active_high_receiver(BitOrder::BigEndian);
+        assert_eq!(Some(Datagram::new("1011")), decode(sut));
+    }
+
+    #[test]
+    fn with_mode_infers_first_bit() {
+        let modes = [
+            (DecodeMode::FirstBitZeroIdleLow, false),
+            (DecodeMode::FirstBitOneIdleLow, true),
+            (DecodeMode::FirstBitOneIdleHigh, true),
+            (DecodeMode::FirstBitZeroIdleHigh, false),
+        ];
+        for (mode, first_bit) in modes {
+            let sut = Decoder::with_mode(mode, BitOrder::BigEndian);
+            assert_eq!(first_bit, sut.inferred_first_bit());
+        }
+        let mut sut = Decoder::with_mode(DecodeMode::FirstBitOneIdleHigh, BitOrder::BigEndian);
+        let mut received = "--------......------...---...----------"
+            .bytes()
+            .filter_map(|sample| sut.next(sample == b'-'));
+        assert_eq!(Some(Datagram::new("1011")), received.next());
+    }
+
+    #[test]
+    fn with_first_bit_selects_edge_synchronization() {
+        let rows = [
+            (ActivityLevel::High, false, DecodeMode::FirstBitZeroIdleLow),
+            (ActivityLevel::High, true, DecodeMode::FirstBitOneIdleLow),
+            (ActivityLevel::Low, true, DecodeMode::FirstBitOneIdleHigh),
+            (ActivityLevel::Low, false, DecodeMode::FirstBitZeroIdleHigh),
+        ];
+        for (activity_level, first_bit, mode) in rows {
+            let sut = Decoder::with_first_bit(activity_level, first_bit, BitOrder::BigEndian);
+            assert_eq!(first_bit, sut.inferred_first_bit());
+            let config = sim::VectorConfig::new(mode, BitOrder::BigEndian);
+            for (datagram, samples) in sim::generate_vectors(config, 5) {
+                let (activity_level, _) = mode.expand();
+                let mut sut = Decoder::with_first_bit(activity_level, first_bit, config.bit_order);
+                let decoded = samples.into_iter().find_map(|sample| sut.next(sample));
+                assert_eq!(Some(datagram), decoded, "{:?}", mode);
+            }
+        }
+    }
+
+    #[test]
+    fn timing_parameters_of_default_configuration() {
+        let sut = Decoder::new(
+            ActivityLevel::High,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        assert_eq!(
+            TimingParameters {
+                lower: 2 * SAMPLES_PER_HALF_BIT_PERIOD - TOLERANCE,
+                upper: 2 * SAMPLES_PER_HALF_BIT_PERIOD + TOLERANCE,
+                exit: 3 * SAMPLES_PER_HALF_BIT_PERIOD,
+            },
+            sut.timing_parameters()
+        );
+        let sut = DecoderBuilder::new(
+            ActivityLevel::High,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_samples_per_half_bit(9)
+        .with_tolerance(2)
+        .build()
+        .unwrap();
+        assert_eq!(
+            TimingParameters {
+                lower: 16,
+                upper: 20,
+                exit: 27,
+            },
+            sut.timing_parameters()
+        );
+    }
+
+    #[test]
+    fn validate_timing_rejects_impossible_configurations() {
+        let mut sut = Decoder::new(
+            ActivityLevel::High,
+            SyncOnTurningEdge::First,
+            BitOrder::LittleEndian,
+        );
+        sut.config.tolerance = 2;
+        assert_eq!(Err(ConfigError::ToleranceTooLarge), sut.validate_timing());
+
+        sut.config.samples_per_half_bit = 0;
+        assert_eq!(
+            Err(ConfigError::ZeroSamplesPerHalfBit),
+            sut.validate_timing()
+        );
+
+        sut.config.samples_per_half_bit = 100;
+        assert_eq!(
+            Err(ConfigError::SamplesPerHalfBitTooLarge),
+            sut.validate_timing()
+        );
+    }
+}
+
+mod capturing_decoder {
+
+    use super::*;
+
+    #[test]
+    fn captured_samples_decode_to_same_datagram() {
+        let mut buffer = [false; 64];
+        let decoder = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let mut sut = CapturingDecoder::new(decoder, &mut buffer);
+        assert!(sut.last_frame_samples().is_empty());
+        let input = "--------......------...---...----------";
+        let mut received = None;
+        for sample in input.bytes() {
+            if let Some(datagram) = sut.next(sample == b'-') {
+                received = Some(datagram);
+            }
+        }
+        assert_eq!(Some(Datagram::new("1011")), received);
+
+        let captured = sut.last_frame_samples();
+        assert!(!captured[0]);
+        let mut decoder = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let (last, leading) = captured.split_last().unwrap();
+        for sample in leading {
+            assert!(decoder.next(*sample).is_none());
+        }
+        assert_eq!(received, decoder.next(*last));
+    }
+
+    #[test]
+    fn samples_are_captured_after_filtering() {
+        let mut buffer = [false; 64];
+        let decoder = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_min_edge_samples(2)
+        .with_startup_blanking(4)
+        .build()
+        .unwrap();
+        let mut sut = CapturingDecoder::new(decoder, &mut buffer);
+        // the blanked start-up edges and the glitch are not captured
+        let input = "..--------....-.------...---...-----------";
+        let received = input.bytes().find_map(|sample| sut.next(sample == b'-'));
+        assert_eq!(Some(Datagram::new("1011")), received);
+
+        let captured = sut.last_frame_samples();
+        assert!(captured[..6].iter().all(|sample| !sample));
+        let mut decoder = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let (last, leading) = captured.split_last().unwrap();
+        for sample in leading {
+            assert!(decoder.next(*sample).is_none());
+        }
+        assert_eq!(received, decoder.next(*last));
+    }
+
+    #[test]
+    fn long_datagram_keeps_the_latest_samples() {
+        let mut buffer = [false; 12];
+        let decoder = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let mut sut = CapturingDecoder::new(decoder, &mut buffer);
+        let input = "--------......------...---...----------";
+        let received = input.bytes().find_map(|sample| sut.next(sample == b'-'));
+        assert_eq!(Some(Datagram::new("1011")), received);
+        assert_eq!(
+            [false, false, true, true, true, true, true, true, true, true, true, true],
+            sut.last_frame_samples()
+        );
+    }
+}
+
+mod decoder_builder {
+
+    use super::*;
+
+    #[test]
+    fn build_default_options() {
+        let sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .build()
+        .unwrap();
+        assert_eq!(SAMPLES_PER_HALF_BIT_PERIOD, sut.config.samples_per_half_bit);
+        assert_eq!(TOLERANCE, sut.config.tolerance);
+    }
+
+    #[test]
+    fn build_rejects_tolerance_conflicting_with_samples() {
+        let sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_samples_per_half_bit(4)
+        .with_tolerance(2)
+        .build();
+        assert_eq!(Some(ConfigError::ToleranceTooLarge), sut.err());
+    }
+
+    fn decode(sut: &mut Decoder, input: &str) -> Option<Datagram> {
+        let mut received = None;
+        for sample in input.bytes() {
+            if let Some(datagram) = sut.next(sample == b'-') {
+                received = Some(datagram);
+            }
+        }
+        received
+    }
+
+    #[test]
+    fn start_idle_window_rejects_too_long_idle() {
+        let frame = "......------...---...----------";
+        let long_idle = "------------------------------";
+
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_start_idle_window(2, 4)
+        .build()
+        .unwrap();
+        assert_eq!(None, decode(&mut sut, long_idle));
+        assert_eq!(None, decode(&mut sut, frame));
+        // the trailing idle of the ignored frame is within the window
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, frame));
+
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_start_idle_window(2, u8::MAX)
+        .build()
+        .unwrap();
+        assert_eq!(None, decode(&mut sut, long_idle));
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, frame));
+    }
+
+    #[test]
+    fn strict_mid_bit_edge_rejects_missing_edge() {
+        let valid = "--------......------...---......-------------";
+        let missing_mid_bit_edge = "--------......------...------...-------------";
+
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_strict_mid_bit_edge(true)
+        .build()
+        .unwrap();
+        assert_eq!(Some(Datagram::new("10110")), decode(&mut sut, valid));
+        let mut events = missing_mid_bit_edge
+            .bytes()
+            .map(|sample| sut.next_event(sample == b'-'))
+            .filter(|event| *event != DecodeEvent::Idle);
+        assert_eq!(
+            Some(DecodeEvent::Error(DecodeError::MissingMidBitEdge)),
+            events.next()
+        );
+        assert_eq!(None, events.next());
+
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let mut events = missing_mid_bit_edge
+            .bytes()
+            .map(|sample| sut.next_event(sample == b'-'))
+            .filter(|event| matches!(event, DecodeEvent::Error(_)));
+        assert_eq!(None, events.next());
+    }
+
+    #[test]
+    fn filter_drops_non_matching_datagrams() {
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_filter(Datagram::new("10"), 4)
+        .build()
+        .unwrap();
+        // 1011
+        assert_eq!(
+            Some(Datagram::new("1011")),
+            decode(&mut sut, "--------......------...---...----------")
+        );
+        // 1100 - wrong prefix
+        assert_eq!(
+            None,
+            decode(&mut sut, "--------...---......---...------------")
+        );
+        // 10110 - wrong length
+        assert_eq!(
+            None,
+            decode(&mut sut, "--------......------...---......-------------")
+        );
+        // 1000
+        assert_eq!(
+            Some(Datagram::new("1000")),
+            decode(&mut sut, "--------......---...---...------------")
+        );
+    }
+
+    #[test]
+    fn startup_blanking_ignores_chattering_input() {
+        let chatter = "-.-..-.--.-";
+        let frame = "--------......------...---...----------";
+        let build = |n| {
+            DecoderBuilder::new(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            )
+            .with_startup_blanking(n)
+            .build()
+            .unwrap()
+        };
+
+        let mut sut = build(chatter.len() as u8);
+        assert_eq!(None, decode(&mut sut, chatter));
+        assert_eq!(ExpectedEvent::FrameStart, sut.expected_next());
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, frame));
+
+        let mut sut = build(0);
+        assert_eq!(None, decode(&mut sut, chatter));
+        assert_ne!(ExpectedEvent::FrameStart, sut.expected_next());
+    }
+
+    #[test]
+    fn edge_one_sample_too_soon_is_reported() {
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_samples_per_half_bit(6)
+        .build()
+        .unwrap();
+        let frame = |short_half_bit: usize| {
+            let mut frame =
+                alloc::format!("{}{}{}", "-".repeat(16), ".".repeat(12), "-".repeat(12));
+            frame.push_str(&".".repeat(short_half_bit));
+            frame.push_str(&alloc::format!(
+                "{}{}{}",
+                "-".repeat(6),
+                ".".repeat(6),
+                "-".repeat(20)
+            ));
+            frame
+        };
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, &frame(6)));
+        // the edge closing the short half bit is one sample earlier than tolerated
+        let events: alloc::vec::Vec<DecodeEvent> = frame(4)
+            .bytes()
+            .map(|sample| sut.next_event(sample == b'-'))
+            .collect();
+        assert_eq!(DecodeEvent::Error(DecodeError::EdgeTooEarly), events[44]);
+        // reporting the early edge does not change what is decoded
+        let mut plain = sut.clone();
+        plain.reset();
+        let decoded = events.iter().find_map(|event| match event {
+            DecodeEvent::Datagram(datagram) => Some(*datagram),
+            _ => None,
+        });
+        assert_eq!(decode(&mut plain, &frame(4)), decoded);
+        // the decoder recovers after the line got idle
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, &frame(6)));
+    }
+
+    #[test]
+    fn capacity_exceeded_is_reported() {
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_max_len(8)
+        .build()
+        .unwrap();
+        // 1 followed by nine zeros
+        let frame = alloc::format!("--------...{}---", "---...".repeat(9));
+        let errors = frame
+            .bytes()
+            .map(|sample| sut.next_event(sample == b'-'))
+            .filter(|event| *event == DecodeEvent::Error(DecodeError::CapacityExceeded))
+            .count();
+        assert_eq!(1, errors);
+    }
+
+    #[test]
+    fn endless_toggling_stream_is_rejected_once() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let toggling = "---...".repeat(4 * Datagram::MAX_BITS as usize);
+        let events: alloc::vec::Vec<DecodeEvent> = alloc::format!("--------...{}", toggling)
+            .bytes()
+            .map(|sample| sut.next_event(sample == b'-'))
+            .filter(|event| *event != DecodeEvent::Idle)
+            .collect();
+        assert_eq!(
+            [DecodeEvent::Error(DecodeError::CapacityExceeded)],
+            events[..]
+        );
+        // the decoder recovers once the line is idle again
+        assert_eq!(
+            Some(Datagram::new("1011")),
+            decode(&mut sut, "----------......------...---...----------")
+        );
+    }
+
+    #[test]
+    fn datagram_beyond_max_bits_is_discarded() {
+        // a one as start bit followed by pseudo random data
+        let samples = |bits: usize| {
+            let mut samples = alloc::string::String::from("-----");
+            for index in 0..bits {
+                let one = index == 0 || (index * 7) % 5 < 2;
+                samples.push_str(if one { "---..." } else { "...---" });
+            }
+            samples.push_str("----------");
+            samples
+        };
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+
+        let longest = decode(&mut sut, &samples(Datagram::MAX_BITS as usize)).unwrap();
+        assert_eq!(Datagram::MAX_BITS, longest.len());
+        assert_eq!(Some(1), longest.get(Datagram::MAX_BITS - 1));
+
+        assert_eq!(
+            None,
+            decode(&mut sut, &samples(Datagram::MAX_BITS as usize + 1))
+        );
+        assert_eq!(
+            None,
+            decode(&mut sut, &samples(3 * Datagram::MAX_BITS as usize))
+        );
+        assert_eq!(2, sut.stats().rejected_frames);
+        assert_eq!(
+            Some(longest),
+            decode(&mut sut, &samples(Datagram::MAX_BITS as usize))
+        );
+    }
+
+    #[test]
+    fn lead_mark_skip_ignores_leading_burst() {
+        let frame = "......------...---...----------";
+        // 8 half bits
+        let burst = "........................";
+        let build = |lead_mark_skip| {
+            let builder = DecoderBuilder::new(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            );
+            match lead_mark_skip {
+                Some(min_half_bits) => builder.with_lead_mark_skip(min_half_bits),
+                None => builder,
+            }
+            .build()
+            .unwrap()
+        };
+        // burst followed by a gap of 4 half bits
+        let signal = alloc::format!("--------{}------------{}", burst, frame);
+
+        let mut sut = build(Some(8));
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, &signal));
+        // the next datagram needs a leading mark again
+        assert_eq!(None, decode(&mut sut, frame));
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, &signal));
+
+        let mut sut = build(Some(12));
+        assert_eq!(None, decode(&mut sut, &signal));
+        let mut sut = build(None);
+        assert_ne!(Some(Datagram::new("1011")), decode(&mut sut, &signal));
+    }
+
+    #[test]
+    fn streaming_window_emits_fixed_size_datagrams() {
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_streaming_window(4)
+        .build()
+        .unwrap();
+        // 1011 1011 10 without any gap
+        let stream =
+            "--------......------...---...---......------...---...---......---------------";
+        let mut received = stream.bytes().filter_map(|sample| sut.next(sample == b'-'));
+        assert_eq!(Some(Datagram::new("1011")), received.next());
+        assert_eq!(Some(Datagram::new("1011")), received.next());
+        assert_eq!(Some(Datagram::new("10")), received.next());
+        assert_eq!(None, received.next());
+        assert_eq!(
+            Some(Datagram::new("1011")),
+            decode(&mut sut, "--------......------...---...----------")
+        );
+    }
+
+    #[test]
+    fn expected_length_emits_without_trailing_idle() {
+        let build = |expected_length: Option<u8>| {
+            let builder = DecoderBuilder::new(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            );
+            match expected_length {
+                Some(bits) => builder.with_expected_length(bits),
+                None => builder,
+            }
+            .build()
+            .unwrap()
+        };
+        let datagram = Datagram::new("11_0101_1000_1101");
+        let mut frame = alloc::string::String::from("--------");
+        for half_bit in Encoder::<DatagramBigEndianIterator>::new(datagram) {
+            frame.push_str(if half_bit { "..." } else { "---" });
+        }
+
+        let mut sut = build(None);
+        assert_eq!(None, decode(&mut sut, &frame));
+        let mut sut = build(Some(14));
+        let mut received = frame.bytes().map(|sample| sut.next(sample == b'-'));
+        assert_eq!(Some(Some(datagram)), received.find(Option::is_some));
+        // the second half of the last bit is still pending
+        assert_eq!(2, received.count());
+
+        // edges after the expected length are ignored until the line is idle
+        let mut sut = build(Some(14));
+        let mut long_frame = frame.clone();
+        long_frame.push_str("---...---...------------");
+        let received: alloc::vec::Vec<Datagram> = long_frame
+            .bytes()
+            .filter_map(|sample| sut.next(sample == b'-'))
+            .collect();
+        assert_eq!(alloc::vec![datagram], received);
+        assert_eq!(Some(datagram), decode(&mut sut, &frame));
+    }
+
+    #[test]
+    fn build_rejects_inconsistent_expected_length() {
+        let builder = || {
+            DecoderBuilder::new(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            )
+        };
+        let sut = builder().with_expected_length(0).build();
+        assert_eq!(Some(ConfigError::ZeroExpectedLength), sut.err());
+        let sut = builder()
+            .with_expected_length(14)
+            .with_streaming_window(8)
+            .build();
+        assert_eq!(
+            Some(ConfigError::ExpectedLengthWithStreamingWindow),
+            sut.err()
+        );
+        let sut = builder()
+            .with_expected_length(14)
+            .with_filter(Datagram::new("11"), 13)
+            .build();
+        assert_eq!(Some(ConfigError::ExpectedLengthMismatchesFilter), sut.err());
+        let sut = builder()
+            .with_expected_length(14)
+            .with_filter(Datagram::new("11"), 14)
+            .build();
+        assert!(sut.is_ok());
+    }
+
+    #[test]
+    fn decode_all_yields_every_frame_of_a_capture() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let frame = "--------......------...---...----------";
+        let capture: alloc::vec::Vec<bool> = frame
+            .bytes()
+            .cycle()
+            .take(3 * frame.len())
+            .map(|sample| sample == b'-')
+            .collect();
+        let received: heapless::Vec<Datagram, 4> =
+            sut.decode_all(capture.iter().copied()).collect();
+        assert_eq!(&[Datagram::new("1011"); 3], received.as_slice());
+        assert_eq!(None, sut.decode_all(capture[..20].iter().copied()).next());
+    }
+
+    #[test]
+    fn reset_discards_partial_frame() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let frame = "--------......------...---...----------";
+        let decode = |sut: &mut Decoder, signal: &str| {
+            signal.bytes().find_map(|sample| sut.next(sample == b'-'))
+        };
+        assert_eq!(None, decode(&mut sut, &frame[..20]));
+        sut.reset();
+        assert_eq!(ExpectedEvent::FrameStart, sut.expected_next());
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, &frame[8..]));
+    }
+
+    #[test]
+    fn with_sampling_decodes_same_frame_at_different_densities() {
+        let frame = "--------......------...---...----------";
+        let decode_at = |samples_per_half_bit: usize| {
+            let mut sut = Decoder::with_sampling(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+                samples_per_half_bit as u8,
+            )
+            .unwrap();
+            let factor = samples_per_half_bit / 3;
+            frame
+                .bytes()
+                .flat_map(|sample| core::iter::repeat(sample == b'-').take(factor))
+                .find_map(|sample| sut.next(sample))
+        };
+        assert_eq!(Some(Datagram::new("1011")), decode_at(3));
+        assert_eq!(decode_at(3), decode_at(6));
+    }
+
+    #[test]
+    fn set_tolerance_accepts_late_mid_bit_edge() {
+        // the mid-bit edge of the second bit is two samples late
+        let frame = [
+            "-".repeat(16),
+            ".".repeat(14),
+            "-".repeat(10),
+            ".".repeat(6),
+            "-".repeat(6),
+            ".".repeat(6),
+            "-".repeat(20),
+        ]
+        .concat();
+        let decode_with = |tolerance| {
+            let mut sut = Decoder::with_sampling(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+                6,
+            )
+            .unwrap();
+            sut.set_tolerance(tolerance).unwrap();
+            frame.bytes().find_map(|sample| sut.next(sample == b'-'))
+        };
+        assert_eq!(Some(Datagram::new("1011")), decode_with(2));
+        assert_ne!(Some(Datagram::new("1011")), decode_with(1));
+    }
+
+    #[test]
+    fn stuck_active_level_is_reported() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let frame = "--------......------...---..............";
+        let errors = frame
+            .bytes()
+            .map(|sample| sut.next_event(sample == b'-'))
+            .filter(|event| *event == DecodeEvent::Error(DecodeError::NoEdgeTimeout))
+            .count();
+        assert_eq!(1, errors);
+    }
+
+    #[test]
+    fn emit_timing_deferred_returns_datagram_one_call_later() {
+        let build = |timing| {
+            DecoderBuilder::new(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            )
+            .with_emit_timing(timing)
+            .build()
+            .unwrap()
+        };
+        let frame = "--------......------...---...--------------";
+        let call_index = |mut sut: Decoder| {
+            frame
+                .bytes()
+                .position(|sample| sut.next(sample == b'-').is_some())
+                .unwrap()
+        };
+        let immediate = call_index(build(EmitTiming::Immediate));
+        let deferred = call_index(build(EmitTiming::Deferred));
+        assert_eq!(immediate + 1, deferred);
+
+        let mut sut = build(EmitTiming::Deferred);
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, frame));
+    }
+
+    #[test]
+    fn emit_timing_deferred_keeps_errors_following_a_datagram() {
+        let build = |timing| {
+            DecoderBuilder::new(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            )
+            .with_streaming_window(2)
+            .with_emit_timing(timing)
+            .build()
+            .unwrap()
+        };
+        // the window completes right before the early edges
+        let frame = "--------......-.----------------";
+        let events = |mut sut: Decoder| {
+            frame
+                .bytes()
+                .map(|sample| sut.next_event(sample == b'-'))
+                .filter(|event| *event != DecodeEvent::Idle)
+                .collect::<alloc::vec::Vec<_>>()
+        };
+        let datagram = DecodeEvent::Datagram(Datagram::new("10"));
+        let error = DecodeEvent::Error(DecodeError::EdgeTooEarly);
+        assert_eq!(
+            alloc::vec![datagram, error, error],
+            events(build(EmitTiming::Immediate))
+        );
+        assert_eq!(
+            alloc::vec![error, error, datagram],
+            events(build(EmitTiming::Deferred))
+        );
+    }
+
+    #[test]
+    fn conventions_round_trip_through_encoder() {
+        let datagram = Datagram::new("1011_0010");
+        for (convention, sync) in [
+            (Convention::GeThomas, SyncOnTurningEdge::First),
+            (Convention::Ieee8023, SyncOnTurningEdge::Second),
+        ] {
+            let encoder =
+                Encoder::<DatagramBigEndianIterator>::new(datagram).with_convention(convention);
+            let mut sut = DecoderBuilder::new(ActivityLevel::Low, sync, BitOrder::BigEndian)
+                .with_convention(convention)
+                .build()
+                .unwrap();
+            let decoded = core::iter::repeat(true)
+                .take(9)
+                .chain(encoder.flat_map(|half_bit| core::iter::repeat(!half_bit).take(3)))
+                .chain(core::iter::repeat(true).take(12))
+                .find_map(|sample| sut.next(sample));
+            assert_eq!(Some(datagram), decoded, "{:?}", convention);
+        }
+    }
+
+    #[test]
+    fn differential_encodings_decode_known_pattern() {
+        let build = |encoding| {
+            DecoderBuilder::new(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            )
+            .with_encoding(encoding)
+            .build()
+            .unwrap()
+        };
+        // reference clock edge at 8, then clock edges every 6 samples with
+        // additional edges at 11, 23 and 29
+        let frame = "--------...---......---...---...------------";
+        let mut sut = build(Encoding::DifferentialMark);
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, frame));
+        let mut sut = build(Encoding::DifferentialSpace);
+        assert_eq!(Some(Datagram::new("0100")), decode(&mut sut, frame));
+        // the level does not matter, the inverted signal decodes the same
+        // (after the line settled at the low level)
+        let inverted: alloc::string::String = "----------"
+            .chars()
+            .chain(frame.chars())
+            .map(|sample| if sample == '-' { '.' } else { '-' })
+            .collect();
+        let mut sut = build(Encoding::DifferentialMark);
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, &inverted));
+    }
+
+    #[test]
+    fn idle_heartbeat_fires_once_per_idle_period() {
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_idle_heartbeat(20)
+        .build()
+        .unwrap();
+        let mut timeouts = |signal: &str| {
+            signal
+                .bytes()
+                .map(|sample| sut.next_event(sample == b'-'))
+                .filter(|event| *event == DecodeEvent::IdleTimeout)
+                .count()
+        };
+        let long_idle = "-".repeat(200);
+        assert_eq!(1, timeouts(&long_idle));
+        assert_eq!(0, timeouts(&long_idle));
+        assert_eq!(0, timeouts("......------...---...----------"));
+        assert_eq!(1, timeouts(&long_idle));
+        // an idle line at the active level is no idle period
+        assert_eq!(0, timeouts(&".".repeat(200)));
+    }
+
+    #[test]
+    fn idle_heartbeat_follows_a_completion_on_the_same_sample() {
+        // the datagram completes after 9 idle samples, i.e. three half bits
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_idle_samples(8)
+        .with_idle_heartbeat(3)
+        .build()
+        .unwrap();
+        let signal = "--------......------...---...--------------------";
+        let events: alloc::vec::Vec<DecodeEvent> = signal
+            .bytes()
+            .map(|sample| sut.next_event(sample == b'-'))
+            .filter(|event| *event != DecodeEvent::Idle)
+            .collect();
+        assert_eq!(
+            [
+                DecodeEvent::Datagram(Datagram::new("1011")),
+                DecodeEvent::IdleTimeout
+            ],
+            events[..]
+        );
+    }
+
+    #[test]
+    fn auto_sync_returns_validating_interpretation() {
+        let frame = "--------......------...---...----------";
+        let build = || {
+            DecoderBuilder::new(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::Second,
+                BitOrder::BigEndian,
+            )
+            .with_filter(Datagram::default(), 4)
+            .build()
+            .unwrap()
+        };
+
+        let mut sut = build();
+        assert_eq!(None, decode(&mut sut, frame));
+
+        let mut sut = build().with_auto_sync();
+        let mut received = frame.bytes().filter_map(|sample| sut.next(sample == b'-'));
+        assert_eq!(Some(Datagram::new("1011")), received.next());
+        assert_eq!(None, received.next());
+    }
+
+    #[test]
+    fn auto_sync_yields_one_datagram_per_transmission() {
+        // both interpretations complete two bits, at different samples
+        let frame = "--------......------...---...----------";
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_expected_length(2)
+        .build()
+        .unwrap()
+        .with_auto_sync();
+        let received: alloc::vec::Vec<Datagram> = frame
+            .bytes()
+            .filter_map(|sample| sut.next(sample == b'-'))
+            .collect();
+        assert_eq!(alloc::vec![Datagram::new("10")], received);
+        assert_eq!(Some(Datagram::new("10")), sut.decoder().take_completed());
+        // the next transmission is decoded again
+        assert_eq!(
+            Some(Datagram::new("10")),
+            frame.bytes().find_map(|sample| sut.next(sample == b'-'))
+        );
+    }
+
+    #[test]
+    fn max_len_drops_longer_datagrams() {
+        let builder = || {
+            DecoderBuilder::new(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            )
+            .with_max_len(32)
+        };
+        let signal = |datagram| {
+            let mut signal = alloc::string::String::from("--------");
+            for half_bit in Encoder::<DatagramBigEndianIterator>::new(datagram) {
+                signal.push_str(if half_bit { "..." } else { "---" });
+            }
+            signal.push_str("----------");
+            signal
+        };
+        let mut sut = builder().build().unwrap();
+        let datagram = Datagram::from_value_min(0xdead_beef);
+        assert_eq!(Some(datagram), decode(&mut sut, &signal(datagram)));
+        let datagram = Datagram::from_value_min(0x1_dead_beef);
+        assert_eq!(None, decode(&mut sut, &signal(datagram)));
+        let datagram = Datagram::new("1011");
+        assert_eq!(Some(datagram), decode(&mut sut, &signal(datagram)));
+
+        let sut = builder().with_filter(Datagram::default(), 33).build();
+        assert_eq!(Some(ConfigError::ExceedsMaxLen), sut.err());
+        let sut = builder().with_streaming_window(33).build();
+        assert_eq!(Some(ConfigError::ExceedsMaxLen), sut.err());
+        let sut = builder().with_expected_length(33).build();
+        assert_eq!(Some(ConfigError::ExceedsMaxLen), sut.err());
+        let sut = builder().with_max_len(0).build();
+        assert_eq!(Some(ConfigError::InvalidMaxLen), sut.err());
+        let sut = builder().with_max_len(129).build();
+        assert_eq!(Some(ConfigError::InvalidMaxLen), sut.err());
+    }
+
+    #[test]
+    fn three_samples_per_half_bit_tolerate_one_sample_jitter() {
+        // one sample of jitter around the mid-bit edges at the default timing
+        let mut sut = Decoder::for_active_low_receiver(BitOrder::BigEndian);
+        let frame = "--------......------...---...----------";
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, frame));
+        let short_bit = "--------......------..---...----------";
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, short_bit));
+        let short_bit = "--------......------...--...----------";
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, short_bit));
+    }
+
+    #[test]
+    fn decode_at_two_samples_per_half_bit() {
+        let frame = "--------....----..--..--------";
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_samples_per_half_bit(2)
+        .with_tolerance(0)
+        .build()
+        .unwrap();
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, frame));
+        // consecutive zeros with an edge at each bit boundary
+        assert_eq!(
+            Some(Datagram::new("1000")),
+            decode(&mut sut, "--------....--..--..----------")
+        );
+
+        let sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_samples_per_half_bit(2)
+        .build();
+        assert_eq!(Some(ConfigError::ToleranceTooLarge), sut.err());
+        let sut = Decoder::with_sampling(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+            2,
+        );
+        assert_eq!(Some(ConfigError::ToleranceTooLarge), sut.err());
+    }
+
+    #[test]
+    fn cloned_decoder_continues_independently() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        // "10" received so far
+        assert_eq!(None, decode(&mut sut, "--------......---"));
+        let mut snapshot = sut.clone();
+        assert_eq!(2, snapshot.progress());
+        assert_eq!(
+            Some(Datagram::new("1011")),
+            decode(&mut sut, "---...---...----------")
+        );
+        assert_eq!(
+            Some(Datagram::new("1000")),
+            decode(&mut snapshot, "...---...-------------")
+        );
+        assert_eq!(0, sut.progress());
+    }
+
+    #[test]
+    fn idle_samples_delay_the_end_of_datagram() {
+        let build = |idle_samples| {
+            DecoderBuilder::new(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            )
+            .with_idle_samples(idle_samples)
+            .build()
+            .unwrap()
+        };
+        // the last edge is at index 29, followed by a long tail gap
+        let mut signal = alloc::string::String::from("--------......------...---...");
+        for _ in 0..30 {
+            signal.push('-');
+        }
+        let completed_at = |mut sut: Decoder| {
+            signal
+                .bytes()
+                .position(|sample| sut.next(sample == b'-').is_some())
+        };
+        assert_eq!(Some(38), completed_at(build(9)));
+        assert_eq!(Some(49), completed_at(build(20)));
+        assert_eq!(None, completed_at(build(30)));
+        let mut sut = build(20);
+        assert_eq!(None, decode(&mut sut, &signal[..49]));
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, "-"));
+    }
+
+    #[test]
+    fn build_rejects_unusable_idle_samples() {
+        let builder = || {
+            DecoderBuilder::new(
+                ActivityLevel::Low,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            )
+        };
+        let sut = builder().with_idle_samples(255).build();
+        assert_eq!(Some(ConfigError::IdleSamplesTooLarge), sut.err());
+        assert!(builder().with_idle_samples(254).build().is_ok());
+        // the upper barrier of the default configuration is 7
+        let sut = builder().with_idle_samples(7).build();
+        assert_eq!(Some(ConfigError::BarrierExceedsExitLimit), sut.err());
+        assert!(builder().with_idle_samples(8).build().is_ok());
+    }
+
+    #[test]
+    fn stats_count_completed_rejected_and_out_of_tolerance() {
+        let mut sut = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let clean = "--------......------...---...----------";
+        // the second edge is late beyond the upper barrier, sync is lost
+        let late_edge = "--------........---...----------";
+        // edges sooner than a half bit are decoded anyway
+        let burst = "--------......-.----------";
+        // the line is stuck at the active level
+        let stuck = "--------......------...---..............";
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, clean));
+        assert_eq!(
+            DecoderStats {
+                rejected_frames: 0,
+                out_of_tolerance_edges: 0,
+                completed_frames: 1,
+            },
+            sut.stats()
+        );
+        decode(&mut sut, late_edge);
+        assert_eq!(Some(Datagram::new("10")), decode(&mut sut, burst));
+        assert_eq!(None, decode(&mut sut, stuck));
+        assert_eq!(
+            DecoderStats {
+                rejected_frames: 1,
+                out_of_tolerance_edges: 4,
+                completed_frames: 3,
+            },
+            sut.stats()
+        );
+        sut.reset_stats();
+        assert_eq!(DecoderStats::default(), sut.stats());
+        assert_eq!(None, decode(&mut sut, stuck));
+        sut.reset();
+        assert_eq!(DecoderStats::default(), sut.stats());
+    }
+
+    #[test]
+    fn build_rejects_filter_prefix_longer_than_length() {
+        let sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_filter(Datagram::new("10110"), 4)
+        .build();
+        assert_eq!(Some(ConfigError::FilterPrefixTooLong), sut.err());
+    }
+
+    #[test]
+    fn build_rejects_inverted_start_idle_window() {
+        let sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_start_idle_window(5, 4)
+        .build();
+        assert_eq!(Some(ConfigError::InvalidStartIdleWindow), sut.err());
+    }
+
+    #[test]
+    fn build_rejects_zero_samples() {
+        let sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_samples_per_half_bit(0)
+        .with_tolerance(0)
+        .build();
+        assert_eq!(Some(ConfigError::ZeroSamplesPerHalfBit), sut.err());
+    }
+
+    #[test]
+    fn min_edge_samples_ignores_single_sample_spikes() {
+        // spikes in the leading idle and in the middle of a full bit period
+        let spiky = "---.----......---.--...---...-------------";
+        let mut unfiltered = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        assert_ne!(Some(Datagram::new("1011")), decode(&mut unfiltered, spiky));
+
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_min_edge_samples(2)
+        .build()
+        .unwrap();
+        assert_eq!(Some(Datagram::new("1011")), decode(&mut sut, spiky));
+        assert_eq!(
+            Some(Datagram::new("1011")),
+            decode(&mut sut, "--------......------...---...-------------")
+        );
+    }
+
+    #[test]
+    fn build_rejects_min_edge_samples_filtering_half_bits() {
+        let sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_min_edge_samples(3)
+        .build();
+        assert_eq!(Some(ConfigError::MinEdgeSamplesTooLarge), sut.err());
+    }
+
+    // deterministic noise of up to 300 in both directions
+    fn noise(index: usize) -> i32 {
+        [0, 170, -150, 300, -300, 90, -220, 260][index % 8]
+    }
+
+    #[test]
+    fn threshold_suppresses_noise_on_a_ramp() {
+        let levels = |threshold: Threshold| {
+            let mut level = false;
+            (0..100)
+                .map(|index| (30 * index + noise(index as usize)).max(0) as u16)
+                .filter(|sample| {
+                    let previous = level;
+                    level = threshold.level(*sample, previous);
+                    level != previous
+                })
+                .count()
+        };
+        assert_eq!(1, levels(Threshold::new(1000, 2000)));
+        assert!(levels(Threshold::new(1500, 1500)) > 1);
+    }
+
+    #[test]
+    fn next_analog_ignores_noise_within_hysteresis() {
+        let mut sut = DecoderBuilder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        )
+        .with_threshold(Threshold::new(1000, 2000))
+        .build()
+        .unwrap();
+        let signal = "--------......------...---...-------------".as_bytes();
+        let mut decoded = alloc::vec::Vec::new();
+        for (index, sample) in signal.iter().enumerate() {
+            // a slow comparator is still in between the levels after an edge
+            let level = if index > 0 && signal[index - 1] != *sample {
+                1500
+            } else if *sample == b'-' {
+                3000
+            } else {
+                400
+            };
+            decoded.extend(sut.next_analog((level + noise(index)) as u16));
+        }
+        assert_eq!([Datagram::new("1011")], decoded[..]);
+        assert_eq!(0, sut.stats().out_of_tolerance_edges);
+    }
+}
+
+mod edge_log_decoder {
+
+    use super::*;
+
+    #[test]
+    fn last_frame_edges_of_back_to_back_datagrams() {
+        let decoder = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let mut sut = decoder.with_edge_log::<8>();
+        assert!(sut.last_frame_edges().is_empty());
+        let input = "--------......------...---...------------...---......---...----------";
+        let mut received = 0;
+        for sample in input.bytes() {
+            if let Some(datagram) = sut.next(sample == b'-') {
+                received += 1;
+                if received == 1 {
+                    assert_eq!(Datagram::new("1011"), datagram);
+                    // boundary and mid-bit edges
+                    assert_eq!([8, 14, 20, 23, 26, 29], sut.last_frame_edges());
+                }
+            }
+        }
+        assert_eq!(2, received);
+        assert_eq!([41, 44, 47, 53, 56, 59], sut.last_frame_edges());
+    }
+
+    #[test]
+    fn edges_beyond_capacity_are_dropped() {
+        let decoder = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        let mut sut = decoder.with_edge_log::<2>();
+        for sample in "--------......------...---...----------".bytes() {
+            sut.next(sample == b'-');
+        }
+        assert_eq!([8, 14], sut.last_frame_edges());
+    }
+}
+
+mod dma_emitter {
+
+    use super::*;
+
+    /// Consumes one half bit per simulated timer event
+    #[derive(Default)]
+    struct MockDma {
+        emitted: heapless::Vec<bool, 64>,
+        pending: heapless::Vec<bool, 64>,
+    }
+
+    impl MockDma {
+        fn timer_event(&mut self) {
+            if !self.pending.is_empty() {
+                let half_bit = self.pending.remove(0);
+                self.emitted.push(half_bit).unwrap();
+            }
+        }
+    }
+
+    impl HalfBitDma for MockDma {
+        fn start(&mut self, half_bits: &[bool]) {
+            self.pending = heapless::Vec::from_slice(half_bits).unwrap();
+        }
+
+        fn remaining(&self) -> usize {
+            self.pending.len()
+        }
+    }
+
+    #[test]
+    fn render_into_too_small_buffer() {
+        let mut half_bits = [false; 3];
+        let encoder = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("01"));
+        assert_eq!(None, encoder.render_into(&mut half_bits));
+    }
+
+    #[test]
+    fn arm_from_buffer_after_completion() {
+        let mut half_bits = [false; 16];
+        let encoder = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("011"));
+        let count = encoder.render_into(&mut half_bits).unwrap();
+        assert_eq!(6, count);
+
+        let mut sut = DmaEmitter::new(MockDma::default());
+        assert!(sut.is_transmission_complete());
+        assert!(sut.arm_from_buffer(&half_bits[..count]));
+        for _ in 0..5 {
+            sut.dma.timer_event();
+            assert!(!sut.is_transmission_complete());
+            assert!(!sut.arm_from_buffer(&half_bits[..count]));
+        }
+        sut.dma.timer_event();
+        assert!(sut.is_transmission_complete());
+        assert!(sut.arm_from_buffer(&half_bits[..count]));
+        for _ in 0..6 {
+            sut.dma.timer_event();
+        }
+        let dma = sut.release();
+        assert_eq!(
+            [true, false, false, true, false, true, true, false, false, true, false, true],
+            dma.emitted.as_slice()
+        );
+    }
+}
+
+mod pin_decoder {
+
+    use super::*;
+
+    /// Plays back a signal; `x` is a sample that fails to read
+    struct MockPin {
+        signal: &'static [u8],
+        index: core::cell::Cell<usize>,
+    }
+
+    impl InputPin for MockPin {
+        type Error = ();
+
+        fn is_high(&self) -> Result<bool, ()> {
+            let sample = self.signal[self.index.get()];
+            self.index.set(self.index.get() + 1);
+            match sample {
+                b'x' => Err(()),
+                sample => Ok(sample == b'-'),
+            }
+        }
+
+        fn is_low(&self) -> Result<bool, ()> {
+            self.is_high().map(|high| !high)
+        }
+    }
+
+    fn pin_decoder(signal: &'static str) -> PinDecoder<MockPin> {
+        let pin = MockPin {
+            signal: signal.as_bytes(),
+            index: core::cell::Cell::new(0),
+        };
+        let decoder = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        PinDecoder::new(pin, decoder)
+    }
+
+    #[test]
+    fn poll() {
+        let signal = "--------......------...---...----------";
+        let mut sut = pin_decoder(signal);
+        for _ in 1..signal.len() {
+            assert_eq!(None, sut.poll());
+        }
+        assert_eq!(Some(Datagram::new("1011")), sut.poll());
+    }
+
+    #[test]
+    fn read_error_drops_datagram_in_progress() {
+        let signal = "--------......---x--...---...----------";
+        let mut sut = pin_decoder(signal);
+        let mut errors = 0;
+        for _ in 0..signal.len() {
+            let event = sut.poll_ext();
+            if event == DecodeEvent::Error(DecodeError::PinReadFailed) {
+                errors += 1;
+            }
+            assert_ne!(DecodeEvent::Datagram(Datagram::new("1011")), event);
+        }
+        assert_eq!(1, errors);
+    }
+
+    #[test]
+    fn read_error_keeps_stats() {
+        let signal = concat!(
+            "--------......------...---...----------",
+            "......---x--...---...----------",
+            "......------...---...----------",
+        );
+        let mut sut = pin_decoder(signal);
+        let received: alloc::vec::Vec<Datagram> =
+            (0..signal.len()).filter_map(|_| sut.poll()).collect();
+        assert_eq!(alloc::vec![Datagram::new("1011"); 2], received);
+        let (_, decoder) = sut.release();
+        assert_eq!(
+            DecoderStats {
+                rejected_frames: 1,
+                out_of_tolerance_edges: 0,
+                completed_frames: 2,
+            },
+            decoder.stats()
+        );
+    }
+}
+
+mod infrared_emitter {
+
+    use super::*;
+
+    /// Records what is done to a two channel PWM
+    #[derive(Default)]
+    struct MockPwm {
+        enabled: [bool; 2],
+        enable_count: [u8; 2],
+        duty: [u16; 2],
+    }
+
+    impl Pwm for MockPwm {
+        type Channel = usize;
+        type Time = u32;
+        type Duty = u16;
+
+        fn disable(&mut self, channel: usize) {
+            self.enabled[channel] = false;
+        }
+
+        fn enable(&mut self, channel: usize) {
+            self.enabled[channel] = true;
+            self.enable_count[channel] += 1;
+        }
+
+        fn get_period(&self) -> u32 {
+            1000
+        }
+
+        fn get_duty(&self, channel: usize) -> u16 {
+            self.duty[channel]
+        }
+
+        fn get_max_duty(&self) -> u16 {
+            1000
+        }
+
+        fn set_duty(&mut self, channel: usize, duty: u16) {
+            self.duty[channel] = duty;
+        }
+
+        fn set_period<P>(&mut self, _period: P)
+        where
+            P: Into<u32>,
+        {
+        }
+    }
+
+    /// A PWM with a duty that supports no arithmetic
+    struct OpaqueDutyPwm;
+
+    impl Pwm for OpaqueDutyPwm {
+        type Channel = ();
+        type Time = ();
+        type Duty = ();
+
+        fn disable(&mut self, _channel: ()) {}
+
+        fn enable(&mut self, _channel: ()) {}
+
+        fn get_period(&self) {}
+
+        fn get_duty(&self, _channel: ()) {}
+
+        fn get_max_duty(&self) {}
+
+        fn set_duty(&mut self, _channel: (), _duty: ()) {}
+
+        fn set_period<P>(&mut self, _period: P)
+        where
+            P: Into<()>,
+        {
+        }
+    }
+
+    #[test]
+    fn queue_and_channel_need_no_duty_arithmetic() {
+        let mut sut = InfraredEmitter::new(0, OpaqueDutyPwm, ());
+        assert_eq!(
+            Ok(()),
+            sut.queue(Datagram::new("1"), BitOrder::BigEndian, ())
+        );
+        assert!(!sut.is_sending());
+        assert!(sut.set_channel(()));
+        assert_eq!(EmitterStats::default(), sut.stats());
+    }
+
+    #[test]
+    fn sending_power_is_a_capped_percentage_of_max_duty() {
+        let mut sut = InfraredEmitter::new(0, MockPwm::default(), 0);
+        for (percent, duty) in [(10, 100), (25, 250), (26, 250), (100, 250), (0, 0)] {
+            assert!(sut.send_if_possible(Datagram::new("1"), BitOrder::BigEndian, percent));
+            assert_eq!(duty, sut.pwm.duty[0], "{} %", percent);
+            for _ in 0..3 {
+                sut.send_half_bit();
+            }
+        }
+    }
+
+    #[test]
+    fn queued_datagrams_are_sent_in_order_with_pause() {
+        let mut sut = InfraredEmitter::new(2, MockPwm::default(), 0);
+        assert_eq!(
+            Ok(()),
+            sut.queue(Datagram::new("1"), BitOrder::BigEndian, 10)
+        );
+        assert_eq!(
+            Ok(()),
+            sut.queue(Datagram::new("10"), BitOrder::BigEndian, 10)
+        );
+        assert_eq!(
+            Ok(()),
+            sut.queue(Datagram::new("11"), BitOrder::BigEndian, 20)
+        );
+        assert_eq!(
+            Ok(()),
+            sut.queue(Datagram::new("0"), BitOrder::BigEndian, 25)
+        );
+        assert_eq!(
+            Err(Datagram::new("01")),
+            sut.queue(Datagram::new("01"), BitOrder::BigEndian, 25)
+        );
+        assert!(!sut.send_if_possible(Datagram::new("01"), BitOrder::BigEndian, 25));
+        let mut timeline = alloc::string::String::new();
+        for _ in 0..27 {
+            sut.send_half_bit();
+            timeline.push(if sut.pwm.enabled[0] { '-' } else { '.' });
+        }
+        // pause, "1", pause, "10", pause, "11", pause, "0"
+        assert_eq!("..", &timeline[..2]);
+        assert_eq!(".-...", &timeline[2..7]);
+        assert_eq!(".--....", &timeline[7..14]);
+        assert_eq!(".-.-...", &timeline[14..21]);
+        assert_eq!("-.....", &timeline[21..]);
+        assert_eq!(250, sut.pwm.duty[0]);
+        assert_eq!(4, sut.stats().frames_sent);
+    }
+
+    #[test]
+    fn status_flags_follow_send_and_pause() {
+        let mut sut = InfraredEmitter::new(2, MockPwm::default(), 0);
+        let mut phases = alloc::vec::Vec::new();
+        for step in 0..7 {
+            if step == 2 {
+                assert!(sut.send_if_possible(Datagram::new("1"), BitOrder::BigEndian, 25));
+                assert!(sut.is_sending());
+            }
+            sut.send_half_bit();
+            phases.push((sut.is_sending(), sut.is_in_pause()));
+        }
+        assert_eq!(
+            [
+                (false, true),
+                (false, false),
+                // two half bits
+                (true, false),
+                (true, false),
+                // pause
+                (false, true),
+                (false, true),
+                (false, false)
+            ],
+            phases[..]
+        );
+    }
+
+    #[test]
+    fn send_on_two_channels() {
+        let mut sut = InfraredEmitter::new(0, MockPwm::default(), 0);
+        assert!(sut.send_if_possible(Datagram::new("1"), BitOrder::BigEndian, 25));
+        assert!(!sut.set_channel(1));
+        sut.send_half_bit();
+        sut.send_half_bit();
+        assert!(sut.pwm.enabled[0]);
+        sut.send_half_bit();
+        assert!(!sut.pwm.enabled[0]);
+
+        assert!(sut.set_channel(1));
+        assert!(sut.send_if_possible(Datagram::new("01"), BitOrder::BigEndian, 25));
+        for _ in 0..5 {
+            sut.send_half_bit();
+        }
+        assert_eq!([1, 2], sut.pwm.enable_count);
+        assert_eq!([false, false], sut.pwm.enabled);
+    }
+
+    #[test]
+    fn stats_count_frames_half_bits_and_pauses() {
+        let mut sut = InfraredEmitter::new(2, MockPwm::default(), 0);
+        assert_eq!(EmitterStats::default(), sut.stats());
+        sut.send_half_bit();
+        sut.send_half_bit();
+        assert!(sut.send_if_possible(Datagram::new("10"), BitOrder::BigEndian, 25));
+        for _ in 0..5 {
+            sut.send_half_bit();
+        }
+        assert!(!sut.send_if_possible(Datagram::new("1"), BitOrder::BigEndian, 25));
+        sut.send_half_bit();
+        sut.send_half_bit();
+        assert!(sut.send_if_possible(Datagram::new("1"), BitOrder::BigEndian, 25));
+        for _ in 0..3 {
+            sut.send_half_bit();
+        }
+        assert_eq!(
+            EmitterStats {
+                frames_sent: 2,
+                half_bits_emitted: 6,
+                pauses: 2,
+            },
+            sut.stats()
+        );
+        sut.reset_stats();
+        assert_eq!(EmitterStats::default(), sut.stats());
+    }
+
+    #[test]
+    fn send_in_both_bit_orders_from_one_emitter() {
+        let mut sut = InfraredEmitter::new(0, MockPwm::default(), 0);
+        let mut line = alloc::string::String::new();
+        assert!(sut.send_if_possible(Datagram::new("10"), BitOrder::BigEndian, 25));
+        for _ in 0..5 {
+            sut.send_half_bit();
+            line.push(if sut.pwm.enabled[0] { '-' } else { '.' });
+        }
+        assert!(sut.send_if_possible(Datagram::new("10"), BitOrder::LittleEndian, 25));
+        for _ in 0..5 {
+            sut.send_half_bit();
+            line.push(if sut.pwm.enabled[0] { '-' } else { '.' });
+        }
+        // "10" big endian, then "01" on the line for little endian
+        assert_eq!(".--..-..-.", line);
+    }
+
+    #[test]
+    fn queue_keeps_bit_order_per_datagram() {
+        let mut sut = InfraredEmitter::new(0, MockPwm::default(), 0);
+        assert_eq!(
+            Ok(()),
+            sut.queue(Datagram::new("10"), BitOrder::LittleEndian, 25)
+        );
+        assert_eq!(
+            Ok(()),
+            sut.queue(Datagram::new("10"), BitOrder::BigEndian, 25)
+        );
+        let mut line = alloc::string::String::new();
+        for _ in 0..11 {
+            sut.send_half_bit();
+            line.push(if sut.pwm.enabled[0] { '-' } else { '.' });
+        }
+        // pause, "01" on the line for little endian, pause, "10" big endian
+        assert_eq!(".-..-...--.", line);
+    }
+
+    #[test]
+    fn send_if_possible_refuses_while_sending() {
+        let mut sut = InfraredEmitter::new(0, MockPwm::default(), 0);
+        assert!(sut.send_if_possible(Datagram::new("10"), BitOrder::BigEndian, 25));
+        let mut line = alloc::string::String::new();
+        for _ in 0..5 {
+            // the datagram in flight is neither truncated nor replaced
+            assert!(!sut.send_if_possible(Datagram::new("0"), BitOrder::BigEndian, 25));
+            sut.send_half_bit();
+            line.push(if sut.pwm.enabled[0] { '-' } else { '.' });
+        }
+        assert_eq!(".--..", line);
+        assert!(sut.send_if_possible(Datagram::new("0"), BitOrder::BigEndian, 25));
+    }
+
+    #[test]
+    fn pause_counter_saturates_on_a_long_idle_line() {
+        let mut sut = InfraredEmitter::new(3, MockPwm::default(), 0);
+        // far more idle half bits than the counter holds
+        for _ in 0..1000 {
+            sut.send_half_bit();
+        }
+        assert!(!sut.is_in_pause());
+        assert!(sut.send_if_possible(Datagram::new("1"), BitOrder::BigEndian, 25));
+    }
+
+    #[test]
+    fn send_alternating() {
+        let mut sut = InfraredEmitter::new(1, MockPwm::default(), 0);
+        sut.send_half_bit();
+        assert!(!sut.send_alternating(
+            Datagram::new("1"),
+            Datagram::new("0"),
+            0,
+            BitOrder::BigEndian,
+            25
+        ));
+        assert!(sut.send_alternating(
+            Datagram::new("1"),
+            Datagram::new("0"),
+            4,
+            BitOrder::BigEndian,
+            25
+        ));
+        assert!(!sut.send_if_possible(Datagram::new("1"), BitOrder::BigEndian, 25));
+        let mut line = heapless::Vec::<bool, 20>::new();
+        for _ in 0..20 {
+            sut.send_half_bit();
+            line.push(sut.pwm.enabled[0]).unwrap();
+        }
+        // "1", pause, "0", pause, "1", pause, "0", idle
+        let (t, f) = (true, false);
+        assert_eq!(
+            [f, t, f, f, t, f, f, f, f, t, f, f, t, f, f, f, f, f, f, f],
+            line.as_slice()
+        );
+        assert_eq!(4, sut.pwm.enable_count[0]);
+        assert!(sut.send_if_possible(Datagram::new("1"), BitOrder::BigEndian, 25));
+    }
+}
+
+mod rc5 {
+
+    use super::*;
+    use crate::rc5::{CommandTable, Rc5Encoder, Rc5Frame};
+
+    #[test]
+    fn from_datagram_rejects_malformed_frames() {
+        assert!(Rc5Frame::from_datagram(&Datagram::new("11_1_00101_010000")).is_some());
+        assert!(Rc5Frame::from_datagram(&Datagram::new("01_1_00101_010000")).is_none());
+        assert!(Rc5Frame::from_datagram(&Datagram::new("11_1_00101_01000")).is_none());
+    }
+
+    #[test]
+    fn fields_of_real_button_codes() {
+        // TV standby
+        let standby = Rc5Frame::from_datagram(&Datagram::new("11_0_00000_001100")).unwrap();
+        assert!(!standby.toggle());
+        assert_eq!(0, standby.address());
+        assert_eq!(12, standby.command());
+        // VCR play, key pressed again
+        let play = Rc5Frame::from_datagram(&Datagram::new("11_1_00101_110101")).unwrap();
+        assert!(play.toggle());
+        assert_eq!(5, play.address());
+        assert_eq!(53, play.command());
+    }
+
+    #[test]
+    fn new_frame_round_trips_fields() {
+        let play = Rc5Frame::new(5, 53, true);
+        assert_eq!(Datagram::new("11_1_00101_110101"), play.datagram());
+        assert_eq!(Some(play), Rc5Frame::from_datagram(&play.datagram()));
+        // excess bits are masked
+        assert_eq!(play, Rc5Frame::new(0xe5, 0xf5, true));
+    }
+
+    #[test]
+    fn encoder_half_bits_of_standby() {
+        let mut sut = Rc5Encoder::new();
+        // TV standby: 11_0_00000_001100
+        #[rustfmt::skip]
+        let expected = [
+            false, true, false, true,
+            true, false,
+            true, false, true, false, true, false, true, false, true, false,
+            true, false, true, false, false, true, false, true, true, false, true, false,
+        ];
+        assert!(sut.press(0, 12).eq(expected.iter().copied()));
+        assert!(!sut.toggle());
+        assert!(sut.repeat(0, 12).eq(expected.iter().copied()));
+    }
+
+    #[test]
+    fn encoder_flips_toggle_per_press() {
+        let mut sut = Rc5Encoder::with_toggle(false);
+        let toggled = Encoder::<DatagramBigEndianIterator>::new(Datagram::new("11_1_00101_110101"));
+        assert!(sut.press(5, 53).eq(toggled));
+        assert!(sut.toggle());
+        let untoggled =
+            Encoder::<DatagramBigEndianIterator>::new(Datagram::new("11_0_00101_110101"));
+        assert!(sut.press(5, 53).eq(untoggled));
+        assert!(!sut.toggle());
+    }
+
+    #[test]
+    fn command_table_lookup() {
+        let table = CommandTable::new(&[(12, "standby"), (16, "volume +"), (17, "volume -")]);
+        let volume_up = Rc5Frame::from_datagram(&Datagram::new("11_1_00101_010000")).unwrap();
+        assert_eq!(16, volume_up.command());
+        assert_eq!(Some("volume +"), table.lookup(&volume_up));
+        let mute = Rc5Frame::from_datagram(&Datagram::new("11_1_00101_001101")).unwrap();
+        assert_eq!(None, table.lookup(&mute));
+    }
+}
+
+mod nec {
+
+    use super::*;
+    use crate::nec::{NecDecoder, NecFrame};
+
+    // active low receiver sampled every 100 µs
+    fn runs(runs: &[(bool, usize)]) -> impl Iterator<Item = bool> + '_ {
+        runs.iter()
+            .flat_map(|(mark, samples)| core::iter::repeat(!mark).take(*samples))
+    }
+
+    fn frame(address: u8, command: u8) -> alloc::vec::Vec<(bool, usize)> {
+        let mut frame = alloc::vec![(false, 20), (true, 90), (false, 45)];
+        for byte in [address, !address, command, !command] {
+            for index in 0..8 {
+                let space = if byte & (1 << index) != 0 { 17 } else { 6 };
+                frame.extend([(true, 6), (false, space)]);
+            }
+        }
+        frame.extend([(true, 6), (false, 400)]);
+        frame
+    }
+
+    #[test]
+    fn decodes_frame_and_repeat_code() {
+        let mut sut = NecDecoder::new(ActivityLevel::Low, 100);
+        let decoded: alloc::vec::Vec<NecFrame> = runs(&frame(0x04, 0x08))
+            .filter_map(|sample| sut.next(sample))
+            .collect();
+        assert_eq!(
+            [NecFrame::Command {
+                address: 0x04,
+                command: 0x08
+            }],
+            decoded[..]
+        );
+
+        let repeat = [(true, 90), (false, 22), (true, 6), (false, 400)];
+        let decoded: alloc::vec::Vec<NecFrame> = runs(&repeat)
+            .filter_map(|sample| sut.next(sample))
+            .collect();
+        assert_eq!([NecFrame::Repeat], decoded[..]);
+    }
+
+    #[test]
+    fn rejects_corrupt_inverted_byte() {
+        let mut sut = NecDecoder::new(ActivityLevel::Low, 100);
+        let mut corrupt = frame(0x04, 0x08);
+        // the first bit of the inverted address turns from one to zero
+        corrupt[3 + 2 * 8 + 1].1 = 6;
+        assert_eq!(None, runs(&corrupt).find_map(|sample| sut.next(sample)));
+        assert!(runs(&frame(0x10, 0x20))
+            .find_map(|sample| sut.next(sample))
+            .is_some());
+    }
+}
+
+mod rc6 {
+
+    use super::*;
+    use crate::rc6::{Rc6Decoder, Rc6Frame};
+
+    // active low receiver sampled every 100 µs
+    fn runs(runs: &[(bool, usize)]) -> impl Iterator<Item = bool> + '_ {
+        runs.iter()
+            .flat_map(|(mark, samples)| core::iter::repeat(!mark).take(*samples))
+            .chain(core::iter::repeat(true).take(50))
+    }
+
+    // mode 0 frame sampled every 111 µs, i.e. 4 samples per unit
+    fn frame(mode: u8, toggle: bool, address: u8, command: u8) -> alloc::vec::Vec<(bool, usize)> {
+        let mut units = alloc::vec![true; 6];
+        units.extend([false; 2]);
+        let mut bit = |value: bool, width: usize| {
+            units.extend(core::iter::repeat(value).take(width / 2));
+            units.extend(core::iter::repeat(!value).take(width / 2));
+        };
+        bit(true, 2);
+        for index in (0..3).rev() {
+            bit(mode & 1 << index != 0, 2);
+        }
+        bit(toggle, 4);
+        for byte in [address, command] {
+            for index in (0..8).rev() {
+                bit(byte & 1 << index != 0, 2);
+            }
+        }
+        let mut runs: alloc::vec::Vec<(bool, usize)> = alloc::vec::Vec::new();
+        for mark in units {
+            match runs.last_mut() {
+                Some((level, samples)) if *level == mark => *samples += 4,
+                _ => runs.push((mark, 4)),
+            }
+        }
+        runs
+    }
+
+    #[test]
+    fn decodes_captured_mode_0_frame() {
+        // TV standby, receiver output stretches marks by about 60 µs
+        #[rustfmt::skip]
+        let captured = [
+            (true, 27), (false, 8),
+            (true, 5), (false, 8), (true, 5), (false, 4), (true, 5), (false, 4),
+            (true, 5), (false, 8), (true, 9), (false, 4), (true, 5), (false, 4),
+            (true, 5), (false, 4), (true, 5), (false, 4), (true, 5), (false, 4),
+            (true, 5), (false, 4), (true, 5), (false, 4), (true, 5), (false, 4),
+            (true, 5), (false, 4), (true, 5), (false, 4), (true, 5), (false, 4),
+            (true, 5), (false, 4), (true, 9), (false, 4), (true, 5), (false, 8),
+            (true, 5), (false, 4), (true, 5),
+        ];
+        let mut sut = Rc6Decoder::new(ActivityLevel::Low, 100);
+        let decoded: alloc::vec::Vec<Rc6Frame> = runs(&captured)
+            .filter_map(|sample| sut.next(sample))
+            .collect();
+        assert_eq!(
+            [Rc6Frame {
+                toggle: false,
+                address: 0,
+                command: 12
+            }],
+            decoded[..]
+        );
+    }
+
+    #[test]
+    fn decodes_wide_toggle_bit_and_trailing_space() {
+        let mut sut = Rc6Decoder::new(ActivityLevel::Low, 111);
+        for (toggle, address, command) in [(true, 0x05, 0x0d), (false, 0xa5, 0xff), (true, 0, 0)] {
+            let decoded =
+                runs(&frame(0, toggle, address, command)).find_map(|sample| sut.next(sample));
+            assert_eq!(
+                Some(Rc6Frame {
+                    toggle,
+                    address,
+                    command
+                }),
+                decoded
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_other_modes_and_broken_timing() {
+        let mut sut = Rc6Decoder::new(ActivityLevel::Low, 111);
+        assert_eq!(
+            None,
+            runs(&frame(6, false, 0x05, 0x0d)).find_map(|sample| sut.next(sample))
+        );
+        let mut broken = frame(0, false, 0x05, 0x0d);
+        // a bit in the middle of the address is stretched by half a unit
+        broken[12].1 += 2;
+        assert_eq!(None, runs(&broken).find_map(|sample| sut.next(sample)));
+        assert!(runs(&frame(0, false, 0x05, 0x0d))
+            .find_map(|sample| sut.next(sample))
+            .is_some());
+    }
+}
+
+mod simulation {
+
+    use super::*;
+
+    fn samples(signal: &str) -> impl Iterator<Item = bool> + '_ {
+        signal.bytes().map(|sample| sample == b'-')
+    }
+
+    #[test]
+    fn round_trip_reproduces_datagrams() {
+        let datagrams = [
+            Datagram::new("1"),
+            Datagram::new("0"),
+            Datagram::new("1011"),
+            Datagram::new("0100"),
+            Datagram::new("11_0000_0000_1100"),
+            Datagram::new("0000_0000_0000_0001"),
+            Datagram::from_value_min(0xdead_beef),
+            Datagram::from_raw(u128::MAX >> 1, 127),
+        ];
+        for mode in [
+            DecodeMode::FirstBitZeroIdleLow,
+            DecodeMode::FirstBitOneIdleLow,
+            DecodeMode::FirstBitOneIdleHigh,
+            DecodeMode::FirstBitZeroIdleHigh,
+        ] {
+            let first_bit = matches!(
+                mode,
+                DecodeMode::FirstBitOneIdleLow | DecodeMode::FirstBitOneIdleHigh
+            );
+            for bit_order in [BitOrder::BigEndian, BitOrder::LittleEndian] {
+                for samples_per_half_bit in [2, 3, 4, 5, 8] {
+                    for datagram in datagrams.iter().filter(|datagram| {
+                        DatagramEitherIterator::new(**datagram, bit_order).next() == Some(first_bit)
+                    }) {
+                        let (activity_level, sync) = mode.expand();
+                        assert_eq!(
+                            Some(*datagram),
+                            sim::round_trip(
+                                datagram,
+                                samples_per_half_bit,
+                                activity_level,
+                                sync,
+                                bit_order
+                            ),
+                            "{:?} {:?} {}",
+                            mode,
+                            bit_order,
+                            samples_per_half_bit
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_without_sampling() {
+        let datagram = Datagram::new("1011");
+        let decoded = sim::round_trip(
+            &datagram,
+            0,
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        assert_eq!(None, decoded);
+    }
+
+    #[test]
+    fn round_trip_of_empty_datagram() {
+        for activity_level in [ActivityLevel::High, ActivityLevel::Low] {
+            let decoded = sim::round_trip(
+                &Datagram::default(),
+                3,
+                activity_level,
+                SyncOnTurningEdge::First,
+                BitOrder::BigEndian,
+            );
+            assert_eq!(None, decoded, "{:?}", activity_level);
+        }
+    }
+
+    #[test]
+    fn generated_vectors_round_trip() {
+        for mode in [
+            DecodeMode::FirstBitZeroIdleLow,
+            DecodeMode::FirstBitOneIdleLow,
+            DecodeMode::FirstBitOneIdleHigh,
+            DecodeMode::FirstBitZeroIdleHigh,
+        ] {
+            for bit_order in [BitOrder::BigEndian, BitOrder::LittleEndian] {
+                let config = sim::VectorConfig::new(mode, bit_order);
+                let mut count = 0;
+                for (datagram, samples) in sim::generate_vectors(config, 6) {
+                    let mut decoder = Decoder::with_mode(mode, bit_order);
+                    let decoded: alloc::vec::Vec<Datagram> = samples
+                        .into_iter()
+                        .filter_map(|sample| decoder.next(sample))
+                        .collect();
+                    assert_eq!([datagram], decoded[..], "{:?} {:?}", mode, bit_order);
+                    count += 1;
+                }
+                assert_eq!(63, count);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 128 bits")]
+    fn generate_vectors_rejects_overlong_datagrams() {
+        let config = sim::VectorConfig::new(DecodeMode::FirstBitOneIdleHigh, BitOrder::BigEndian);
+        let _ = sim::generate_vectors(config, 129);
+    }
+
+    #[test]
+    fn decode_with_gaps_reports_elapsed_time() {
+        let mut decoder = Decoder::new(
+            ActivityLevel::Low,
+            SyncOnTurningEdge::First,
+            BitOrder::BigEndian,
+        );
+        // each frame completes on its last sample
+        let frame = "--------......------...---...----------";
+        let idle = "--------------------";
+        let capture = samples(frame).chain(samples(idle)).chain(samples(frame));
+        let mut sut = sim::decode_with_gaps(&mut decoder, capture, 296);
+        assert_eq!(Some((Datagram::new("1011"), 39 * 296)), sut.next());
+        assert_eq!(Some((Datagram::new("1011"), 59 * 296)), sut.next());
+        assert_eq!(None, sut.next());
+    }
+
+    #[test]
+    fn detect_rate_changes() {
+        let frame = "--------......------...---...----------";
+        let slow_frame: alloc::string::String = frame.chars().flat_map(|c| [c, c]).collect();
+        let signal = alloc::format!("{}{}{}", frame, slow_frame, frame);
+        let changes = sim::detect_rate_changes::<_, 4>(samples(&signal), 3);
+        assert_eq!([(frame.len() + 16, 6)], changes.as_slice());
+
+        let signal = alloc::format!("{}{}", frame, frame);
+        assert!(sim::detect_rate_changes::<_, 4>(samples(&signal), 3).is_empty());
+        assert!(sim::detect_rate_changes::<_, 4>(samples(&slow_frame), 6).is_empty());
+    }
+
+    #[test]
+    fn run_lengths() {
+        let runs: alloc::vec::Vec<(bool, u32)> =
+            sim::run_lengths(samples("--------......------...---...----------")).collect();
+        assert_eq!(
+            [
+                (true, 8),
+                (false, 6),
+                (true, 6),
+                (false, 3),
+                (true, 3),
+                (false, 3),
+                (true, 10)
+            ],
+            runs.as_slice()
+        );
+        assert_eq!(None, sim::run_lengths(samples("")).next());
+    }
+
+    #[test]
+    fn distinguishing_prefix_len() {
+        let commands = [
+            Datagram::new("1100_0001"),
+            Datagram::new("1101_0001"),
+            Datagram::new("1000_1111"),
+            Datagram::new("0000_0000"),
+        ];
+        assert_eq!(Some(4), sim::distinguishing_prefix_len(&commands));
+        assert_eq!(Some(2), sim::distinguishing_prefix_len(&commands[1..]));
+        assert_eq!(Some(0), sim::distinguishing_prefix_len(&commands[..1]));
+        assert_eq!(Some(0), sim::distinguishing_prefix_len(&[]));
+        let same_prefix = [Datagram::new("10"), Datagram::new("101")];
+        assert_eq!(Some(3), sim::distinguishing_prefix_len(&same_prefix));
+        let identical = [commands[0], commands[2], commands[0]];
+        assert_eq!(None, sim::distinguishing_prefix_len(&identical));
+        let full = [
+            Datagram::from_raw(u128::MAX, Datagram::MAX_BITS),
+            Datagram::from_raw(u128::MAX >> 1, Datagram::MAX_BITS),
+        ];
+        assert_eq!(Some(1), sim::distinguishing_prefix_len(&full));
+        assert_eq!(Some(0), sim::distinguishing_prefix_len(&full[..1]));
+    }
+
+    #[test]
+    fn diff_decode() {
+        let signal = "--------......------...---...------------...---......---...----------";
+        let decoder = |sync| Decoder::new(ActivityLevel::Low, sync, BitOrder::BigEndian);
+
+        let (mut a, mut b) = (
+            decoder(SyncOnTurningEdge::First),
+            decoder(SyncOnTurningEdge::First),
+        );
+        assert!(sim::diff_decode::<_, 4>(&mut a, &mut b, samples(signal)).is_empty());
+
+        let (mut a, mut b) = (
+            decoder(SyncOnTurningEdge::First),
+            decoder(SyncOnTurningEdge::Second),
+        );
+        let diffs = sim::diff_decode::<_, 4>(&mut a, &mut b, samples(signal));
+        assert_eq!(2, diffs.len());
+        assert_eq!((38, Datagram::new("1011")), (diffs[0].0, diffs[0].1));
+        assert_eq!(Datagram::new("1100"), diffs[1].1);
+        assert_ne!(diffs[0].1, diffs[0].2);
+        assert_ne!(diffs[1].1, diffs[1].2);
+    }
+
+    #[test]
+    fn to_c_array() {
+        let table = [Datagram::new("1011"), Datagram::new("11_0000_0011_0101")];
+        assert_eq!(
+            concat!(
+                "static const struct { uint8_t len; uint64_t bits; } rc5_commands[] = {\n",
+                "    { 4, 0xbULL },\n",
+                "    { 14, 0x3035ULL },\n",
+                "};\n",
+            ),
+            sim::to_c_array(&table, "rc5_commands")
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn to_c_array_too_long_datagram() {
+        let _ = sim::to_c_array(&[Datagram::alternating(65, true)], "too_long");
+    }
 }